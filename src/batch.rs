@@ -0,0 +1,101 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Multi-get variants of the lookup tools.
+//!
+//! Each item runs through the single-item tool; a failing item is reported
+//! next to its input instead of failing the whole batch.
+
+use serde_json::{json, Value};
+
+use crate::JsonRpcError;
+
+/// Most items accepted in one batch call.
+pub const MAX_ITEMS: usize = 100;
+
+pub struct BatchTool {
+    /// Single-item tool each input is passed to
+    pub tool: &'static str,
+    /// Array argument of the batch tool
+    pub list_key: &'static str,
+    /// Argument of the single-item tool that receives each input
+    pub item_key: &'static str,
+}
+
+/// The single-item tool behind a batch tool name.
+pub fn batch_tool(name: &str) -> Option<BatchTool> {
+    let (tool, list_key, item_key) = match name {
+        "get_symbols" => ("get_symbol", "ids", "id"),
+        "get_files" => ("get_file", "paths", "path"),
+        "get_symbol_usages" => ("get_symbol_usage", "ids", "id"),
+        "get_symbol_sources" => ("get_symbol_source", "ids", "id"),
+        _ => return None,
+    };
+    Some(BatchTool { tool, list_key, item_key })
+}
+
+impl BatchTool {
+    /// The batch inputs, each with the arguments for its single-item call.
+    ///
+    /// Arguments other than the list are shared by every item. Items are
+    /// always run with `format: json` so their output can be embedded.
+    pub fn items(&self, arguments: &Value) -> Result<Vec<(Value, Value)>, JsonRpcError> {
+        let inputs = match arguments.get(self.list_key) {
+            Some(Value::Array(inputs)) if !inputs.is_empty() => inputs,
+            _ => {
+                return Err(JsonRpcError {
+                    code: -32602,
+                    message: format!("Missing {} (expected a non-empty array)", self.list_key),
+                    data: None,
+                })
+            }
+        };
+        if inputs.len() > MAX_ITEMS {
+            return Err(JsonRpcError {
+                code: -32602,
+                message: format!("Too many {}: {} (at most {})", self.list_key, inputs.len(), MAX_ITEMS),
+                data: None,
+            });
+        }
+
+        let mut shared = arguments.as_object().cloned().unwrap_or_default();
+        shared.remove(self.list_key);
+        shared.insert("format".to_string(), json!("json"));
+        Ok(inputs
+            .iter()
+            .map(|input| {
+                let mut item = shared.clone();
+                item.insert(self.item_key.to_string(), input.clone());
+                (input.clone(), Value::Object(item))
+            })
+            .collect())
+    }
+}
+
+/// Output of one item: the single-item tool's parsed output, or its error.
+pub fn item_output(input: Value, result: Result<Value, JsonRpcError>) -> Value {
+    match result {
+        Ok(result) => {
+            let text = result.pointer("/content/0/text").and_then(|t| t.as_str()).unwrap_or_default();
+            let output = serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()));
+            if result.get("isError").and_then(|e| e.as_bool()).unwrap_or(false) {
+                json!({ "input": input, "error": { "message": output } })
+            } else {
+                json!({ "input": input, "result": output })
+            }
+        }
+        Err(error) => json!({ "input": input, "error": error }),
+    }
+}
+
+/// Batch output in input order, with success and failure counts.
+pub fn collect(items: Vec<Value>) -> Value {
+    let failed = items.iter().filter(|i| i.get("error").is_some()).count();
+    json!({
+        "count": items.len(),
+        "succeeded": items.len() - failed,
+        "failed": failed,
+        "items": items
+    })
+}
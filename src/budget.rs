@@ -0,0 +1,314 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Token budgets for tool and resource output.
+//!
+//! With `max_tokens` set, the main list or text in a JSON result is cut to fit
+//! and a `budget` object reports what was dropped, with a `next_cursor` to pass
+//! back as `cursor` for the rest. Plain-text results are cut by lines instead.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::tokens::estimate_tokens;
+use crate::JsonRpcError;
+
+/// Tools that size their own output from `max_tokens`.
+pub const NATIVE_BUDGET_TOOLS: &[&str] = &["get_repo_map", "build_context_pack"];
+
+#[derive(Debug, Clone, Copy)]
+pub struct Budget {
+    pub max_tokens: usize,
+    /// Items (or lines) already returned by earlier pages
+    pub offset: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BudgetReport {
+    pub max_tokens: usize,
+    pub used_tokens: usize,
+    pub returned: usize,
+    pub dropped_items: usize,
+    pub dropped_tokens: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// JSON pointers of nested fields cut to fit, outside the paged list
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub truncated_fields: Vec<String>,
+    /// Still over `max_tokens` after cutting everything that could be cut
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub over_budget: bool,
+}
+
+#[derive(Debug, Default)]
+struct Page {
+    returned: usize,
+    dropped_items: usize,
+    dropped_tokens: usize,
+}
+
+impl Budget {
+    /// Reads `max_tokens` and `cursor`; `None` when no budget was requested.
+    pub fn from_arguments(arguments: &Value) -> Result<Option<Self>, JsonRpcError> {
+        let Some(max_tokens) = arguments.get("max_tokens").filter(|v| !v.is_null()) else {
+            return Ok(None);
+        };
+        let max_tokens = max_tokens.as_u64().filter(|n| *n > 0).ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "max_tokens must be a positive integer".to_string(),
+            data: None,
+        })? as usize;
+
+        let offset = match arguments.get("cursor") {
+            None | Some(Value::Null) => 0,
+            Some(cursor) => cursor
+                .as_str()
+                .and_then(|c| c.parse::<usize>().ok())
+                .or_else(|| cursor.as_u64().map(|c| c as usize))
+                .ok_or_else(|| JsonRpcError {
+                    code: -32602,
+                    message: format!("Invalid cursor: {}", cursor),
+                    data: None,
+                })?,
+        };
+
+        Ok(Some(Self { max_tokens, offset }))
+    }
+
+    /// Fits a tool result's text content to the budget.
+    pub fn apply_to_tool_result(&self, mut result: Value) -> Value {
+        if let Some(text) = result.pointer("/content/0/text").and_then(|t| t.as_str()) {
+            let fitted = self.apply(text);
+            result["content"][0]["text"] = json!(fitted);
+        }
+        result
+    }
+
+    /// Fits a JSON or plain-text result to the budget.
+    ///
+    /// JSON stays valid JSON: it is never cut as text.
+    pub fn apply(&self, text: &str) -> String {
+        let fits = self.offset == 0 && estimate_tokens(text) <= self.max_tokens;
+        match serde_json::from_str::<Value>(text) {
+            // Nothing to page through: keep the value whole if it fits
+            Ok(value) if fits && !has_list(&value) => text.to_string(),
+            Ok(value) => serde_json::to_string_pretty(&self.apply_json(value)).unwrap_or_default(),
+            Err(_) if fits => text.to_string(),
+            Err(_) => self.apply_text(text),
+        }
+    }
+
+    /// Pages the main part of a JSON result and shortens the rest to fit.
+    ///
+    /// The main part is the value itself if it is an array, otherwise its
+    /// largest top-level array or string field (such as the `content` of a
+    /// file); arrays are paged by item and strings by line. Top-level arrays are wrapped as
+    /// `{"items": [...], "budget": {...}}`. At least one item or line is
+    /// always returned so paging makes progress.
+    ///
+    /// If the result is still over budget, the largest nested strings and
+    /// arrays elsewhere are cut, and their JSON pointers are listed in
+    /// `truncated_fields`. `over_budget` is set when that is not enough.
+    pub fn apply_json(&self, value: Value) -> Value {
+        let mut object = match value {
+            Value::Array(items) => {
+                let mut object = serde_json::Map::new();
+                object.insert("items".to_string(), Value::Array(items));
+                object
+            }
+            Value::Object(object) => object,
+            other => return other,
+        };
+
+        let field = object
+            .iter()
+            .filter(|(_, v)| v.is_array() || v.is_string())
+            .max_by_key(|(_, v)| estimate_tokens(&v.to_string()))
+            .map(|(k, _)| k.clone());
+        let Some(field) = field else {
+            return Value::Object(object);
+        };
+
+        let main = object.remove(&field).unwrap_or_default();
+        let base = estimate_tokens(&serde_json::to_string_pretty(&object).unwrap_or_default());
+        let available = self.max_tokens.saturating_sub(base);
+        let (main, page) = match main {
+            Value::String(text) => {
+                let lines: Vec<String> = text.lines().map(|l| format!("{}\n", l)).collect();
+                let (kept, page) = self.page(lines, available, |line| estimate_tokens(line));
+                (Value::String(kept.concat()), page)
+            }
+            Value::Array(items) => {
+                let (kept, page) = self.page(items, available, |item| {
+                    estimate_tokens(&serde_json::to_string_pretty(item).unwrap_or_default())
+                });
+                (Value::Array(kept), page)
+            }
+            other => (other, Page::default()),
+        };
+        object.insert(field.clone(), main);
+
+        let mut value = Value::Object(object);
+        let mut truncated_fields = Vec::new();
+        let mut used = estimate_tokens(&serde_json::to_string_pretty(&value).unwrap_or_default());
+        while used > self.max_tokens {
+            let Some(pointer) = shorten_largest(&mut value, &format!("/{}", escape_pointer(&field))) else {
+                break;
+            };
+            if !truncated_fields.contains(&pointer) {
+                truncated_fields.push(pointer);
+            }
+            used = estimate_tokens(&serde_json::to_string_pretty(&value).unwrap_or_default());
+        }
+
+        let report = BudgetReport {
+            max_tokens: self.max_tokens,
+            used_tokens: used,
+            returned: page.returned,
+            dropped_items: page.dropped_items,
+            dropped_tokens: page.dropped_tokens,
+            next_cursor: (page.dropped_items > 0).then(|| (self.offset + page.returned).to_string()),
+            truncated_fields,
+            over_budget: used > self.max_tokens,
+        };
+        value["budget"] = json!(report);
+        value
+    }
+
+    /// Keeps the items after the cursor that fit in `available` tokens.
+    fn page<T>(&self, items: Vec<T>, available: usize, cost: impl Fn(&T) -> usize) -> (Vec<T>, Page) {
+        let mut kept = Vec::new();
+        let mut page = Page::default();
+        let mut used = 0;
+        for item in items.into_iter().skip(self.offset) {
+            let cost = cost(&item);
+            if page.dropped_items > 0 || (used + cost > available && !kept.is_empty()) {
+                page.dropped_items += 1;
+                page.dropped_tokens += cost;
+                continue;
+            }
+            used += cost;
+            kept.push(item);
+        }
+        page.returned = kept.len();
+        (kept, page)
+    }
+
+    /// Cuts plain text by whole lines and appends a note with the cursor.
+    pub fn apply_text(&self, text: &str) -> String {
+        let lines: Vec<&str> = text.lines().skip(self.offset).collect();
+        let mut used = 0;
+        let mut kept = 0;
+        for line in &lines {
+            let cost = estimate_tokens(line) + 1;
+            if used + cost > self.max_tokens && kept > 0 {
+                break;
+            }
+            used += cost;
+            kept += 1;
+        }
+
+        let mut fitted = lines[..kept].join("\n");
+        if kept < lines.len() {
+            let dropped_tokens: usize = lines[kept..].iter().map(|l| estimate_tokens(l) + 1).sum();
+            fitted.push_str(&format!(
+                "\n[truncated: {} more lines (~{} tokens); cursor: \"{}\"]",
+                lines.len() - kept,
+                dropped_tokens,
+                self.offset + kept
+            ));
+        }
+        fitted
+    }
+}
+
+/// Whether a JSON result has a list to page through.
+fn has_list(value: &Value) -> bool {
+    value.is_array() || value.as_object().is_some_and(|o| o.values().any(Value::is_array))
+}
+
+/// Strings shorter than this (in bytes) are not worth cutting.
+const MIN_CUT_LEN: usize = 64;
+
+/// Halves the largest string or array in `value`, skipping `excluded`
+/// itself (but not its items); returns the JSON pointer of what was cut.
+fn shorten_largest(value: &mut Value, excluded: &str) -> Option<String> {
+    fn largest(value: &Value, pointer: String, excluded: &str, best: &mut Option<(usize, String)>) {
+        let cost = || estimate_tokens(&value.to_string());
+        let cuttable = pointer != excluded
+            && match value {
+                Value::String(text) => text.len() >= MIN_CUT_LEN,
+                Value::Array(items) => items.len() > 1,
+                _ => false,
+            };
+        if cuttable {
+            let cost = cost();
+            if best.as_ref().is_none_or(|(best, _)| cost > *best) {
+                *best = Some((cost, pointer.clone()));
+            }
+        }
+        match value {
+            Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    largest(item, format!("{}/{}", pointer, i), excluded, best);
+                }
+            }
+            Value::Object(object) => {
+                for (key, item) in object {
+                    largest(item, format!("{}/{}", pointer, escape_pointer(key)), excluded, best);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut best = None;
+    largest(value, String::new(), excluded, &mut best);
+    let (_, pointer) = best?;
+    match value.pointer_mut(&pointer)? {
+        Value::String(text) => {
+            let mut cut = text.len() / 2;
+            while !text.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            // Prefer ending on a whole line
+            let cut = text[..cut].rfind('\n').filter(|n| *n > 0).unwrap_or(cut);
+            text.truncate(cut);
+            text.push_str("\n[truncated]");
+        }
+        Value::Array(items) => items.truncate(items.len() / 2),
+        _ => return None,
+    }
+    Some(pointer)
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Adds `max_tokens` and `cursor` to the input schema of every tool without its own budget.
+pub fn add_budget_properties(tools: &mut Value) {
+    for tool in tools.as_array_mut().into_iter().flatten() {
+        if tool["name"].as_str().is_some_and(|name| NATIVE_BUDGET_TOOLS.contains(&name)) {
+            continue;
+        }
+        if let Some(properties) = tool.pointer_mut("/inputSchema/properties").and_then(|p| p.as_object_mut()) {
+            properties.insert(
+                "max_tokens".to_string(),
+                json!({
+                    "type": "integer",
+                    "description": "Approximate token budget for the result; the rest can be fetched with the returned cursor",
+                    "minimum": 1
+                }),
+            );
+            properties.insert(
+                "cursor".to_string(),
+                json!({
+                    "type": "string",
+                    "description": "next_cursor from a previous call with the same arguments"
+                }),
+            );
+        }
+    }
+}
@@ -0,0 +1,221 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Task-driven context bundles: search hits plus their call neighbours, fitted to a token budget.
+
+use adi_core::{Symbol, SymbolId};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::graph::{is_external_path, CallGraph, Direction};
+use crate::source::SourceFile;
+use crate::tokens::estimate_tokens;
+
+/// Relative priority of a neighbour to the hit it was reached from.
+const CALLEE_WEIGHT: f64 = 0.5;
+const CALLER_WEIGHT: f64 = 0.4;
+/// Smallest remaining budget worth spending on a truncated snippet.
+const MIN_PARTIAL_TOKENS: usize = 48;
+
+/// A source range proposed for the pack.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub path: PathBuf,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub priority: f64,
+    pub symbols: Vec<String>,
+    pub reasons: Vec<String>,
+}
+
+impl Candidate {
+    fn new(symbol: &Symbol, priority: f64, reason: String) -> Self {
+        Self {
+            path: symbol.file_path.clone(),
+            start_line: symbol.location.start_line as usize,
+            end_line: symbol.location.end_line as usize,
+            priority,
+            symbols: vec![symbol.name.clone()],
+            reasons: vec![reason],
+        }
+    }
+
+    fn absorb(&mut self, other: Candidate) {
+        self.start_line = self.start_line.min(other.start_line);
+        self.end_line = self.end_line.max(other.end_line);
+        self.priority = self.priority.max(other.priority);
+        for name in other.symbols {
+            if !self.symbols.contains(&name) {
+                self.symbols.push(name);
+            }
+        }
+        for reason in other.reasons {
+            if !self.reasons.contains(&reason) {
+                self.reasons.push(reason);
+            }
+        }
+    }
+}
+
+/// Merges candidates whose line ranges overlap within the same file; highest priority first.
+pub fn merge_overlapping(mut candidates: Vec<Candidate>) -> Vec<Candidate> {
+    candidates.sort_by(|a, b| a.path.cmp(&b.path).then(a.start_line.cmp(&b.start_line)));
+    let mut merged: Vec<Candidate> = Vec::new();
+    for candidate in candidates {
+        match merged.last_mut() {
+            Some(last) if last.path == candidate.path && candidate.start_line <= last.end_line => last.absorb(candidate),
+            _ => merged.push(candidate),
+        }
+    }
+    merged.sort_by(|a, b| b.priority.total_cmp(&a.priority).then(a.path.cmp(&b.path)));
+    merged
+}
+
+#[derive(Debug, Serialize)]
+pub struct Snippet {
+    pub rank: usize,
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub symbols: Vec<String>,
+    /// Why the snippet was included
+    pub reason: String,
+    pub priority: f64,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    pub tokens: usize,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContextPack {
+    pub task: String,
+    pub max_tokens: usize,
+    pub used_tokens: usize,
+    pub snippets: Vec<Snippet>,
+    /// Candidates left out because the budget ran out
+    pub omitted: usize,
+}
+
+/// Fits candidates into `max_tokens`, reading their source through `read`.
+///
+/// A candidate too large for the remaining budget is cut to its first lines
+/// if enough budget is left, and skipped otherwise.
+pub fn pack(
+    task: &str,
+    candidates: Vec<Candidate>,
+    max_tokens: usize,
+    mut read: impl FnMut(&Path) -> Option<SourceFile>,
+) -> ContextPack {
+    let mut files: HashMap<PathBuf, Option<SourceFile>> = HashMap::new();
+    let mut pack = ContextPack {
+        task: task.to_string(),
+        max_tokens,
+        used_tokens: 0,
+        snippets: Vec::new(),
+        omitted: 0,
+    };
+
+    for candidate in candidates {
+        let remaining = max_tokens.saturating_sub(pack.used_tokens);
+        let file = files.entry(candidate.path.clone()).or_insert_with(|| read(&candidate.path));
+        let Some(file) = file.as_ref() else {
+            pack.omitted += 1;
+            continue;
+        };
+
+        let mut span = file.span(&candidate.path, candidate.start_line, candidate.end_line);
+        let mut tokens = estimate_tokens(&span.content);
+        let mut truncated = false;
+        if tokens > remaining {
+            if remaining < MIN_PARTIAL_TOKENS {
+                pack.omitted += 1;
+                continue;
+            }
+            let mut end = span.start_line;
+            while end < span.end_line && estimate_tokens(&file.span(&candidate.path, span.start_line, end + 1).content) <= remaining {
+                end += 1;
+            }
+            span = file.span(&candidate.path, span.start_line, end);
+            tokens = estimate_tokens(&span.content);
+            truncated = true;
+            if tokens > remaining {
+                pack.omitted += 1;
+                continue;
+            }
+        }
+
+        pack.used_tokens += tokens;
+        pack.snippets.push(Snippet {
+            rank: pack.snippets.len() + 1,
+            path: span.path,
+            start_line: span.start_line,
+            end_line: span.end_line,
+            symbols: candidate.symbols,
+            reason: candidate.reasons.join("; "),
+            priority: (candidate.priority * 1000.0).round() / 1000.0,
+            truncated,
+            tokens,
+            content: span.content,
+        });
+    }
+
+    pack
+}
+
+pub struct ContextPackOptions {
+    pub max_tokens: usize,
+    /// Search hits to start from
+    pub hits: usize,
+    /// How many of the top hits to expand through the call graph
+    pub expand: usize,
+    /// Callers and callees taken per expanded hit, in each direction
+    pub neighbours: usize,
+}
+
+/// Builds a context pack for a natural-language task.
+pub async fn context_pack(adi: &adi_core::Adi, task: &str, options: &ContextPackOptions) -> anyhow::Result<ContextPack> {
+    let results = adi.search(task, options.hits).await?;
+    let mut candidates: HashMap<SymbolId, Candidate> = HashMap::new();
+    let mut add = |symbol: &Symbol, priority: f64, reason: String| {
+        let candidate = Candidate::new(symbol, priority, reason);
+        match candidates.get_mut(&symbol.id) {
+            Some(existing) => existing.absorb(candidate),
+            None => {
+                candidates.insert(symbol.id, candidate);
+            }
+        }
+    };
+
+    let mut graph = CallGraph::new(adi);
+    for (rank, result) in results.iter().enumerate() {
+        // Scores are not comparable across backends; fall back to rank order
+        let priority = if result.score > 0.0 { result.score as f64 } else { 1.0 / (rank + 1) as f64 };
+        add(&result.symbol, priority, format!("matches the task (score {:.2})", result.score));
+        graph.insert(result.symbol.clone());
+
+        if rank >= options.expand {
+            continue;
+        }
+        for (direction, weight) in [(Direction::Callees, CALLEE_WEIGHT), (Direction::Callers, CALLER_WEIGHT)] {
+            for id in graph.neighbours(result.symbol.id, direction).into_iter().take(options.neighbours) {
+                let Some(neighbour) = graph.symbol(id).cloned() else {
+                    continue;
+                };
+                if is_external_path(&neighbour.file_path) {
+                    continue;
+                }
+                let reason = match direction {
+                    Direction::Callees => format!("called by `{}`", result.symbol.name),
+                    Direction::Callers => format!("calls `{}`", result.symbol.name),
+                };
+                add(&neighbour, priority * weight, reason);
+            }
+        }
+    }
+
+    let candidates = merge_overlapping(candidates.into_values().collect());
+    Ok(pack(task, candidates, options.max_tokens, |path| SourceFile::read(adi.project_path(), path).ok()))
+}
@@ -0,0 +1,245 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Dependency cycle detection via strongly connected components.
+
+use adi_core::SymbolId;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use crate::deps::{self, DependencyEdge, DependencyOptions, Level};
+use crate::resolve::KeyIndex;
+
+/// A strongly connected component with more than one node.
+#[derive(Debug)]
+pub struct Component<N> {
+    pub nodes: Vec<N>,
+    pub edges: Vec<(N, N, usize)>,
+    /// Back edges of a depth-first walk; removing them breaks every cycle
+    pub closing_edges: Vec<(N, N, usize)>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Cycle {
+    pub size: usize,
+    pub nodes: Vec<String>,
+    pub edges: Vec<DependencyEdge>,
+    pub closing_edges: Vec<DependencyEdge>,
+}
+
+impl<N> Component<N> {
+    pub fn label(&self, mut label: impl FnMut(&N) -> String) -> Cycle {
+        let mut edge = |(from, to, weight): &(N, N, usize)| DependencyEdge {
+            from: label(from),
+            to: label(to),
+            weight: *weight,
+        };
+        let edges = self.edges.iter().map(&mut edge).collect();
+        let closing_edges = self.closing_edges.iter().map(&mut edge).collect();
+        Cycle {
+            size: self.nodes.len(),
+            nodes: self.nodes.iter().map(label).collect(),
+            edges,
+            closing_edges,
+        }
+    }
+}
+
+/// Finds all cycles in a weighted directed graph, largest first.
+///
+/// Self-loops are ignored; parallel edges should already be merged into weights.
+pub fn find_cycles<N: Ord + Clone>(edges: &[(N, N, usize)]) -> Vec<Component<N>> {
+    let mut adjacency: BTreeMap<&N, Vec<(&N, usize)>> = BTreeMap::new();
+    for (from, to, weight) in edges.iter().filter(|(from, to, _)| from != to) {
+        adjacency.entry(from).or_default().push((to, *weight));
+        adjacency.entry(to).or_default();
+    }
+
+    let mut components: Vec<Component<N>> = strongly_connected(&adjacency)
+        .into_iter()
+        .filter(|nodes| nodes.len() > 1)
+        .map(|nodes| {
+            let members: BTreeSet<&N> = nodes.iter().copied().collect();
+            let edges = members
+                .iter()
+                .flat_map(|&from| {
+                    adjacency[from]
+                        .iter()
+                        .filter(|(to, _)| members.contains(to))
+                        .map(move |(to, weight)| (from.clone(), (*to).clone(), *weight))
+                })
+                .collect();
+            let closing_edges = back_edges(&adjacency, &members);
+            Component {
+                nodes: members.into_iter().cloned().collect(),
+                edges,
+                closing_edges,
+            }
+        })
+        .collect();
+
+    components.sort_by(|a, b| {
+        b.nodes
+            .len()
+            .cmp(&a.nodes.len())
+            .then_with(|| b.edges.len().cmp(&a.edges.len()))
+            .then_with(|| a.nodes.cmp(&b.nodes))
+    });
+    components
+}
+
+/// Tarjan's algorithm, iterative so deep graphs cannot overflow the stack.
+fn strongly_connected<'a, N: Ord>(adjacency: &BTreeMap<&'a N, Vec<(&'a N, usize)>>) -> Vec<Vec<&'a N>> {
+    let mut index: BTreeMap<&N, usize> = BTreeMap::new();
+    let mut low: BTreeMap<&N, usize> = BTreeMap::new();
+    let mut on_stack: BTreeSet<&N> = BTreeSet::new();
+    let mut stack: Vec<&N> = Vec::new();
+    let mut components = Vec::new();
+
+    for &root in adjacency.keys() {
+        if index.contains_key(root) {
+            continue;
+        }
+        // (node, position of the next neighbour to visit)
+        let mut work: Vec<(&N, usize)> = vec![(root, 0)];
+        while let Some((node, position)) = work.pop() {
+            if position == 0 {
+                index.insert(node, index.len());
+                low.insert(node, index[node]);
+                stack.push(node);
+                on_stack.insert(node);
+            }
+
+            if let Some(&(next, _)) = adjacency[node].get(position) {
+                work.push((node, position + 1));
+                if !index.contains_key(next) {
+                    work.push((next, 0));
+                } else if on_stack.contains(next) {
+                    let lowest = low[node].min(index[next]);
+                    low.insert(node, lowest);
+                }
+                continue;
+            }
+
+            if low[node] == index[node] {
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack.remove(member);
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+            if let Some(&(parent, _)) = work.last() {
+                let lowest = low[parent].min(low[node]);
+                low.insert(parent, lowest);
+            }
+        }
+    }
+
+    components
+}
+
+/// Edges that point back to a node on the current depth-first path within `members`.
+fn back_edges<N: Ord + Clone>(adjacency: &BTreeMap<&N, Vec<(&N, usize)>>, members: &BTreeSet<&N>) -> Vec<(N, N, usize)> {
+    let mut visited: BTreeSet<&N> = BTreeSet::new();
+    let mut on_path: BTreeSet<&N> = BTreeSet::new();
+    let mut closing = Vec::new();
+
+    for &root in members {
+        if !visited.insert(root) {
+            continue;
+        }
+        on_path.insert(root);
+        let mut work: Vec<(&N, usize)> = vec![(root, 0)];
+        while let Some((node, position)) = work.pop() {
+            let neighbours = &adjacency[node];
+            let Some(i) = (position..neighbours.len()).find(|&i| members.contains(neighbours[i].0)) else {
+                on_path.remove(node);
+                continue;
+            };
+            let (to, weight) = neighbours[i];
+            work.push((node, i + 1));
+            if on_path.contains(to) {
+                closing.push((node.clone(), to.clone(), weight));
+            } else if visited.insert(to) {
+                on_path.insert(to);
+                work.push((to, 0));
+            }
+        }
+    }
+
+    closing
+}
+
+/// Node sets of cycles recorded in a baseline.
+///
+/// Accepts a previous tool result (`{"cycles": [...]}`), a list of cycles, or
+/// a list of node-name lists.
+pub fn baseline_cycles(baseline: &Value) -> Option<Vec<BTreeSet<String>>> {
+    let cycles = baseline.get("cycles").unwrap_or(baseline).as_array()?;
+    cycles
+        .iter()
+        .map(|cycle| {
+            let nodes = cycle.get("nodes").unwrap_or(cycle).as_array()?;
+            nodes.iter().map(|n| n.as_str().map(str::to_string)).collect()
+        })
+        .collect()
+}
+
+/// Resolves a baseline file path, which must be relative and stay inside the
+/// project after following symlinks.
+pub fn baseline_path(project_path: &Path, path: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(path);
+    if relative.is_absolute() || relative.components().any(|c| c == std::path::Component::ParentDir) {
+        return Err(format!("Baseline {} must be a path inside the project", path));
+    }
+    let root = project_path.canonicalize().map_err(|e| format!("Cannot read baseline {}: {}", path, e))?;
+    let full = root.join(relative).canonicalize().map_err(|e| format!("Cannot read baseline {}: {}", path, e))?;
+    if !full.starts_with(&root) {
+        return Err(format!("Baseline {} must be a path inside the project", path));
+    }
+    Ok(full)
+}
+
+/// Whether a cycle is absent from the baseline, i.e. not contained in any baseline cycle.
+pub fn is_new_cycle(cycle: &Cycle, baseline: &[BTreeSet<String>]) -> bool {
+    !baseline.iter().any(|known| cycle.nodes.iter().all(|n| known.contains(n)))
+}
+
+/// Cycles in the index at symbol level (`level` of `None`) or an aggregated level.
+///
+/// Symbols are labelled with their stable keys so results can be compared
+/// across re-indexing.
+pub fn dependency_cycles(adi: &adi_core::Adi, level: Option<Level>, depth: Option<usize>) -> anyhow::Result<Vec<Cycle>> {
+    let Some(level) = level else {
+        let (_, calls) = deps::collect_calls(adi)?;
+        let mut weights: BTreeMap<(SymbolId, SymbolId), usize> = BTreeMap::new();
+        for call in calls {
+            *weights.entry((call.caller, call.callee)).or_default() += 1;
+        }
+        let edges: Vec<(SymbolId, SymbolId, usize)> = weights.into_iter().map(|((from, to), w)| (from, to, w)).collect();
+
+        let mut keys = KeyIndex::new(adi);
+        let mut label = |id: &SymbolId| match adi.get_symbol(*id) {
+            Ok(symbol) => keys.key(&symbol).to_string(),
+            Err(_) => format!("#{}", id.0),
+        };
+        return Ok(find_cycles(&edges).iter().map(|c| c.label(&mut label)).collect());
+    };
+
+    let options = DependencyOptions {
+        level,
+        scope: None,
+        depth,
+        min_weight: 1,
+    };
+    let graph = deps::dependency_graph(adi, &options)?;
+    let edges: Vec<(String, String, usize)> = graph.edges.into_iter().map(|e| (e.from, e.to, e.weight)).collect();
+    Ok(find_cycles(&edges).iter().map(|c| c.label(String::clone)).collect())
+}
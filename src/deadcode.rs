@@ -0,0 +1,186 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Report of symbols nothing in the index refers to.
+
+use adi_core::{Symbol, SymbolId, SymbolKind, Visibility};
+use globset::GlobSet;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::filter::is_test_path;
+
+/// Method names of common standard traits, called implicitly rather than by name.
+const TRAIT_METHODS: &[&str] = &[
+    "fmt", "from", "into", "try_from", "drop", "deref", "deref_mut", "clone", "eq", "ne", "cmp", "partial_cmp", "hash",
+    "default", "next", "into_iter", "from_str", "as_ref", "as_mut", "borrow", "index", "add", "sub", "mul", "div", "neg",
+    "serialize", "deserialize", "__init__", "__str__", "__repr__", "__eq__", "__hash__", "toString", "equals", "hashCode",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl Confidence {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "low" => Some(Confidence::Low),
+            "medium" => Some(Confidence::Medium),
+            "high" => Some(Confidence::High),
+            _ => None,
+        }
+    }
+
+    /// How sure we can be that an unreferenced symbol is really dead.
+    ///
+    /// Call edges cover functions well; types and constants are mostly used
+    /// in ways the index does not record as calls, and public API may be used
+    /// outside the project. `None` for kinds not reported.
+    pub fn of(symbol: &Symbol) -> Option<Self> {
+        let callable = match symbol.kind {
+            SymbolKind::Function | SymbolKind::Method => true,
+            SymbolKind::Constructor
+            | SymbolKind::Class
+            | SymbolKind::Struct
+            | SymbolKind::Enum
+            | SymbolKind::Interface
+            | SymbolKind::Trait
+            | SymbolKind::Type
+            | SymbolKind::Constant => false,
+            _ => return None,
+        };
+        Some(match (callable, symbol.visibility) {
+            (true, Visibility::Private) => Confidence::High,
+            (true, Visibility::Public) => Confidence::Low,
+            (true, _) => Confidence::Medium,
+            (false, _) => Confidence::Low,
+        })
+    }
+}
+
+pub struct DeadCodeOptions {
+    /// Report public API too, at low confidence
+    pub include_public: bool,
+    pub ignore_names: Option<GlobSet>,
+    pub ignore_paths: Option<GlobSet>,
+    pub min_confidence: Confidence,
+}
+
+/// Why a symbol is left out of the report even without callers.
+pub fn exclusion(symbol: &Symbol, parent: Option<&Symbol>, options: &DeadCodeOptions) -> Option<&'static str> {
+    if symbol.is_entry_point || symbol.name == "main" || symbol.name == "__main__" {
+        return Some("entry_point");
+    }
+    if is_test_path(&symbol.file_path)
+        || symbol.name.starts_with("test_")
+        || parent.is_some_and(|p| p.name == "tests" && p.kind == SymbolKind::Module)
+    {
+        return Some("test");
+    }
+    if symbol.kind == SymbolKind::Method {
+        let trait_parent = parent.is_some_and(|p| {
+            matches!(p.kind, SymbolKind::Trait | SymbolKind::Interface)
+                || p.signature.as_deref().is_some_and(|s| s.starts_with("impl") && s.contains(" for "))
+        });
+        if trait_parent || TRAIT_METHODS.contains(&symbol.name.as_str()) {
+            return Some("trait_impl");
+        }
+    }
+    if symbol.visibility == Visibility::Public && !options.include_public {
+        return Some("public_api");
+    }
+    if options.ignore_names.as_ref().is_some_and(|g| g.is_match(&symbol.name))
+        || options.ignore_paths.as_ref().is_some_and(|g| g.is_match(&symbol.file_path))
+    {
+        return Some("ignored");
+    }
+    None
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeadSymbol {
+    pub id: SymbolId,
+    pub name: String,
+    pub kind: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub confidence: Confidence,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeadFile {
+    pub path: String,
+    pub symbols: Vec<DeadSymbol>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeadCodeReport {
+    pub total: usize,
+    pub by_confidence: BTreeMap<Confidence, usize>,
+    /// Unreferenced symbols left out, by reason
+    pub excluded: BTreeMap<&'static str, usize>,
+    pub files: Vec<DeadFile>,
+}
+
+/// Lists unreferenced symbols grouped by file, highest confidence first within each file.
+pub fn dead_code(adi: &adi_core::Adi, options: &DeadCodeOptions) -> anyhow::Result<DeadCodeReport> {
+    let tree = adi.get_tree()?;
+    let mut report = DeadCodeReport {
+        total: 0,
+        by_confidence: BTreeMap::new(),
+        excluded: BTreeMap::new(),
+        files: Vec::new(),
+    };
+
+    for file in &tree.files {
+        let Ok(info) = adi.get_file(&file.path) else {
+            continue;
+        };
+        let by_id: HashMap<SymbolId, &Symbol> = info.symbols.iter().map(|s| (s.id, s)).collect();
+        let mut dead = Vec::new();
+
+        for symbol in &info.symbols {
+            let Some(confidence) = Confidence::of(symbol).filter(|c| *c >= options.min_confidence) else {
+                continue;
+            };
+            let referenced = adi
+                .get_symbol_usage(symbol.id)
+                .map(|u| u.reference_count > 0 || !u.callers.is_empty())
+                .unwrap_or(true);
+            if referenced {
+                continue;
+            }
+            let parent = symbol.parent_id.and_then(|id| by_id.get(&id).copied());
+            if let Some(reason) = exclusion(symbol, parent, options) {
+                *report.excluded.entry(reason).or_default() += 1;
+                continue;
+            }
+
+            *report.by_confidence.entry(confidence).or_default() += 1;
+            dead.push(DeadSymbol {
+                id: symbol.id,
+                name: symbol.name.clone(),
+                kind: symbol.kind.as_str().to_string(),
+                start_line: symbol.location.start_line,
+                end_line: symbol.location.end_line,
+                confidence,
+            });
+        }
+
+        if !dead.is_empty() {
+            dead.sort_by(|a, b| b.confidence.cmp(&a.confidence).then(a.start_line.cmp(&b.start_line)));
+            report.total += dead.len();
+            report.files.push(DeadFile {
+                path: file.path.to_string_lossy().to_string(),
+                symbols: dead,
+            });
+        }
+    }
+
+    Ok(report)
+}
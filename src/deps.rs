@@ -0,0 +1,229 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! File, directory and package dependency graphs aggregated from symbol call edges.
+
+use adi_core::SymbolId;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::graph;
+
+/// Files whose presence marks a directory as a package root.
+const PACKAGE_MANIFESTS: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "setup.py",
+    "go.mod",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    File,
+    Directory,
+    Package,
+}
+
+impl Level {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "file" => Some(Level::File),
+            "directory" | "dir" | "module" => Some(Level::Directory),
+            "package" => Some(Level::Package),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct DependencyNode {
+    pub name: String,
+    pub files: usize,
+    /// Distinct nodes depending on this one
+    pub fan_in: usize,
+    /// Distinct nodes this one depends on
+    pub fan_out: usize,
+    pub references_in: usize,
+    pub references_out: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DependencyEdge {
+    pub from: String,
+    pub to: String,
+    /// Number of symbol references from `from` into `to`
+    pub weight: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DependencyGraph {
+    pub level: Level,
+    pub nodes: Vec<DependencyNode>,
+    pub edges: Vec<DependencyEdge>,
+}
+
+pub struct DependencyOptions {
+    pub level: Level,
+    /// Keep only references with at least one end under this path
+    pub scope: Option<PathBuf>,
+    /// Directory level only: truncate directories to this many components
+    pub depth: Option<usize>,
+    pub min_weight: usize,
+}
+
+/// Maps files to the node they belong to at the requested level.
+pub struct Grouping<'a> {
+    project_path: &'a Path,
+    level: Level,
+    depth: Option<usize>,
+    /// Whether each directory seen so far is a package root
+    packages: HashMap<PathBuf, bool>,
+}
+
+impl<'a> Grouping<'a> {
+    pub fn new(project_path: &'a Path, level: Level, depth: Option<usize>) -> Self {
+        Self {
+            project_path,
+            level,
+            depth,
+            packages: HashMap::new(),
+        }
+    }
+
+    pub fn group(&mut self, path: &Path) -> String {
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let name = match self.level {
+            Level::File => return path.to_string_lossy().to_string(),
+            Level::Directory => match self.depth {
+                Some(depth) => dir.components().take(depth).collect(),
+                None => dir.to_path_buf(),
+            },
+            Level::Package => self.package_root(dir),
+        };
+        if name.as_os_str().is_empty() {
+            ".".to_string()
+        } else {
+            name.to_string_lossy().to_string()
+        }
+    }
+
+    /// Nearest ancestor of `dir` containing a package manifest, or the project root.
+    fn package_root(&mut self, dir: &Path) -> PathBuf {
+        for ancestor in dir.ancestors() {
+            let is_package = *self.packages.entry(ancestor.to_path_buf()).or_insert_with(|| {
+                PACKAGE_MANIFESTS
+                    .iter()
+                    .any(|m| self.project_path.join(ancestor).join(m).is_file())
+            });
+            if is_package {
+                return ancestor.to_path_buf();
+            }
+        }
+        PathBuf::new()
+    }
+}
+
+/// Aggregates per-reference `(caller file, callee file)` pairs into a graph.
+///
+/// Every file in `files` becomes (part of) a node even without edges;
+/// references inside a single node are not edges.
+pub fn aggregate(
+    files: &[PathBuf],
+    references: &[(PathBuf, PathBuf)],
+    grouping: &mut Grouping,
+    options: &DependencyOptions,
+) -> DependencyGraph {
+    let in_scope = |path: &Path| options.scope.as_ref().is_none_or(|s| path.starts_with(s));
+    let mut nodes: BTreeMap<String, DependencyNode> = BTreeMap::new();
+    let mut weights: BTreeMap<(String, String), usize> = BTreeMap::new();
+
+    for (from, to) in references.iter().filter(|(from, to)| in_scope(from) || in_scope(to)) {
+        let (from, to) = (grouping.group(from), grouping.group(to));
+        if from != to {
+            *weights.entry((from, to)).or_default() += 1;
+        }
+    }
+    weights.retain(|_, weight| *weight >= options.min_weight);
+
+    let connected: HashSet<&String> = weights.keys().flat_map(|(from, to)| [from, to]).collect();
+    for file in files {
+        let name = grouping.group(file);
+        if in_scope(file) || connected.contains(&name) {
+            nodes.entry(name.clone()).or_insert_with(|| DependencyNode { name, ..Default::default() }).files += 1;
+        }
+    }
+
+    let mut edges = Vec::new();
+    for ((from, to), weight) in weights {
+        for (name, outgoing) in [(&from, true), (&to, false)] {
+            let node = nodes.entry(name.clone()).or_insert_with(|| DependencyNode {
+                name: name.clone(),
+                ..Default::default()
+            });
+            if outgoing {
+                node.fan_out += 1;
+                node.references_out += weight;
+            } else {
+                node.fan_in += 1;
+                node.references_in += weight;
+            }
+        }
+        edges.push(DependencyEdge { from, to, weight });
+    }
+    edges.sort_by(|a, b| b.weight.cmp(&a.weight).then_with(|| (&a.from, &a.to).cmp(&(&b.from, &b.to))));
+
+    DependencyGraph {
+        level: options.level,
+        nodes: nodes.into_values().collect(),
+        edges,
+    }
+}
+
+/// A resolved call from one indexed symbol to another.
+#[derive(Debug, Clone)]
+pub struct Call {
+    pub caller: SymbolId,
+    pub caller_file: PathBuf,
+    pub callee: SymbolId,
+    pub callee_file: PathBuf,
+}
+
+/// All indexed files and every call between indexed symbols.
+pub fn collect_calls(adi: &adi_core::Adi) -> anyhow::Result<(Vec<PathBuf>, Vec<Call>)> {
+    let tree = adi.get_tree()?;
+    let mut files = Vec::new();
+    let mut calls = Vec::new();
+
+    for file in &tree.files {
+        files.push(file.path.clone());
+        let mut ids = Vec::new();
+        graph::collect_ids(&file.symbols, &mut ids);
+        for id in ids {
+            for callee in adi.get_callees(id).unwrap_or_default() {
+                calls.push(Call {
+                    caller: id,
+                    caller_file: file.path.clone(),
+                    callee: callee.id,
+                    callee_file: callee.file_path,
+                });
+            }
+        }
+    }
+
+    Ok((files, calls))
+}
+
+/// Builds the dependency graph for the whole index.
+pub fn dependency_graph(adi: &adi_core::Adi, options: &DependencyOptions) -> anyhow::Result<DependencyGraph> {
+    let (files, calls) = collect_calls(adi)?;
+    let references: Vec<(PathBuf, PathBuf)> = calls.into_iter().map(|c| (c.caller_file, c.callee_file)).collect();
+    let mut grouping = Grouping::new(adi.project_path(), options.level, options.depth);
+    Ok(aggregate(&files, &references, &mut grouping, options))
+}
@@ -0,0 +1,286 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Near-duplicate functions: embedding neighbours confirmed by comparing
+//! normalized token sequences, clustered and ranked by consolidation payoff.
+
+use adi_core::{Symbol, SymbolId, SymbolKind};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::filter::SearchFilter;
+use crate::similar::{embedding_text, is_same_symbol};
+use crate::source::SourceFile;
+
+/// Tokens per shingle when comparing sequences.
+const SHINGLE: usize = 4;
+
+/// Kept verbatim when normalizing; every other identifier becomes `$`.
+const KEYWORDS: &[&str] = &[
+    "fn", "def", "function", "func", "return", "if", "else", "elif", "for", "while", "loop", "match", "switch", "case",
+    "break", "continue", "let", "var", "const", "mut", "pub", "class", "struct", "impl", "self", "this", "new", "try",
+    "catch", "except", "finally", "raise", "throw", "await", "async", "yield", "in", "is", "not", "and", "or", "none",
+    "null", "nil", "true", "false", "unsafe", "static", "where", "with", "lambda", "defer", "go", "select", "do",
+];
+
+/// Token sequence with identifiers, numbers and string literals replaced by
+/// placeholders and comments dropped, so renamed copies compare equal.
+pub fn normalize_tokens(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with("//") || line.starts_with("# ") || line == "#" {
+            continue;
+        }
+        let chars: Vec<char> = line.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            if c.is_whitespace() {
+                i += 1;
+            } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+                break;
+            } else if c.is_alphabetic() || c == '_' {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect::<String>().to_lowercase();
+                tokens.push(if KEYWORDS.contains(&word.as_str()) { word } else { "$".to_string() });
+            } else if c.is_ascii_digit() {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '.' || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push("0".to_string());
+            } else if c == '\'' && is_lifetime(&chars, i) {
+                tokens.push("'".to_string());
+                i += 1;
+            } else if c == '"' || c == '\'' || c == '`' {
+                i += 1;
+                while i < chars.len() && chars[i] != c {
+                    i += if chars[i] == '\\' { 2 } else { 1 };
+                }
+                i += 1;
+                tokens.push("\"\"".to_string());
+            } else {
+                tokens.push(c.to_string());
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+/// Whether the `'` at `i` starts a Rust lifetime or loop label (`'a`,
+/// `'static`, `'outer:`) rather than a literal.
+///
+/// `'x'` and `'\n'` are char literals. A name not closed right after it is a
+/// lifetime when no quote follows on the line, or when it sits where only
+/// lifetimes do: after `<`, `&` or `+`, or before `>` or `:`.
+fn is_lifetime(chars: &[char], i: usize) -> bool {
+    let start = i + 1;
+    if !chars.get(start).is_some_and(|c| c.is_alphabetic() || *c == '_') {
+        return false;
+    }
+    let mut end = start;
+    while end < chars.len() && (chars[end].is_alphanumeric() || chars[end] == '_') {
+        end += 1;
+    }
+    if chars.get(end) == Some(&'\'') {
+        return false;
+    }
+    let before = chars[..i].iter().rev().find(|c| !c.is_whitespace());
+    !chars[end..].contains(&'\'') || matches!(before, Some('<' | '&' | '+')) || matches!(chars.get(end), Some('>' | ':'))
+}
+
+/// Jaccard similarity of the token shingles of two normalized sequences.
+pub fn token_similarity(a: &[String], b: &[String]) -> f64 {
+    if a.len() < SHINGLE || b.len() < SHINGLE {
+        return if a == b { 1.0 } else { 0.0 };
+    }
+    let shingles = |tokens: &[String]| -> HashSet<Vec<String>> { tokens.windows(SHINGLE).map(|w| w.to_vec()).collect() };
+    let (a, b) = (shingles(a), shingles(b));
+    let shared = a.intersection(&b).count();
+    shared as f64 / (a.len() + b.len() - shared) as f64
+}
+
+/// Connected components over similar pairs, as sorted member indices; singletons are dropped.
+pub fn cluster(count: usize, pairs: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let mut parent: Vec<usize> = (0..count).collect();
+    fn find(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for &(a, b) in pairs {
+        let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+        if ra != rb {
+            parent[ra.max(rb)] = ra.min(rb);
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for i in 0..count {
+        let root = find(&mut parent, i);
+        groups.entry(root).or_default().push(i);
+    }
+    groups.into_values().filter(|g| g.len() > 1).collect()
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateMember {
+    pub id: SymbolId,
+    pub name: String,
+    pub kind: String,
+    pub path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub lines: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateCluster {
+    pub size: usize,
+    /// Mean token-sequence similarity of the confirmed pairs
+    pub similarity: f64,
+    /// Mean embedding similarity of the confirmed pairs
+    pub embedding_similarity: f64,
+    /// Lines saved by keeping only the largest member
+    pub consolidatable_lines: usize,
+    pub members: Vec<DuplicateMember>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateReport {
+    /// Functions compared
+    pub candidates: usize,
+    pub cluster_count: usize,
+    pub clusters: Vec<DuplicateCluster>,
+}
+
+pub struct DuplicateOptions {
+    pub min_lines: usize,
+    /// Token-sequence similarity a pair needs to count as duplicates
+    pub min_similarity: f64,
+    /// Embedding neighbours compared per function
+    pub neighbours: usize,
+    /// Largest functions considered; each costs one embedding query
+    pub max_candidates: usize,
+    pub max_clusters: usize,
+    pub filter: SearchFilter,
+}
+
+struct Candidate {
+    symbol: Symbol,
+    lines: usize,
+    tokens: Vec<String>,
+    body: String,
+}
+
+/// Clusters near-duplicate functions and methods across the index.
+pub async fn find_duplicates(adi: &adi_core::Adi, options: &DuplicateOptions) -> anyhow::Result<DuplicateReport> {
+    let tree = adi.get_tree()?;
+    let mut candidates = Vec::new();
+    for file in &tree.files {
+        if !options.filter.matches_path(&file.path) || !options.filter.matches_language(file.language.as_str()) {
+            continue;
+        }
+        let (Ok(info), Ok(source)) = (adi.get_file(&file.path), SourceFile::read(adi.project_path(), &file.path)) else {
+            continue;
+        };
+        for symbol in info.symbols {
+            let (start, end) = (symbol.location.start_line as usize, symbol.location.end_line as usize);
+            let lines = (end + 1).saturating_sub(start);
+            if !matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method) || lines < options.min_lines {
+                continue;
+            }
+            let body = source.lines(start, end);
+            candidates.push(Candidate {
+                tokens: normalize_tokens(&body),
+                symbol,
+                lines,
+                body,
+            });
+        }
+    }
+    candidates.sort_by(|a, b| b.lines.cmp(&a.lines).then(a.symbol.id.cmp(&b.symbol.id)));
+    candidates.truncate(options.max_candidates);
+    let index: HashMap<SymbolId, usize> = candidates.iter().enumerate().map(|(i, c)| (c.symbol.id, i)).collect();
+
+    // (token similarity, embedding similarity) per confirmed pair, smaller index first
+    let mut pairs: HashMap<(usize, usize), (f64, f64)> = HashMap::new();
+    for (i, candidate) in candidates.iter().enumerate() {
+        let query = embedding_text(&candidate.symbol, Some(&candidate.body));
+        let results = adi.search(&query, options.neighbours + 1).await?;
+        for result in results.iter().filter(|r| !is_same_symbol(&candidate.symbol, &r.symbol)) {
+            let Some(&j) = index.get(&result.symbol.id) else {
+                continue;
+            };
+            let key = (i.min(j), i.max(j));
+            if pairs.contains_key(&key) {
+                continue;
+            }
+            let similarity = token_similarity(&candidate.tokens, &candidates[j].tokens);
+            if similarity >= options.min_similarity {
+                pairs.insert(key, (similarity, result.score as f64));
+            }
+        }
+    }
+
+    let edges: Vec<(usize, usize)> = pairs.keys().copied().collect();
+    let mut clusters: Vec<DuplicateCluster> = cluster(candidates.len(), &edges)
+        .into_iter()
+        .map(|members| {
+            let scores: Vec<(f64, f64)> = pairs
+                .iter()
+                .filter(|((a, _), _)| members.contains(a))
+                .map(|(_, scores)| *scores)
+                .collect();
+            let mean = |f: fn(&(f64, f64)) -> f64| (scores.iter().map(f).sum::<f64>() / scores.len() as f64 * 1000.0).round() / 1000.0;
+            let total: usize = members.iter().map(|&m| candidates[m].lines).sum();
+            let largest = members.iter().map(|&m| candidates[m].lines).max().unwrap_or(0);
+
+            let mut members: Vec<DuplicateMember> = members
+                .iter()
+                .map(|&m| {
+                    let symbol = &candidates[m].symbol;
+                    DuplicateMember {
+                        id: symbol.id,
+                        name: symbol.name.clone(),
+                        kind: symbol.kind.as_str().to_string(),
+                        path: symbol.file_path.to_string_lossy().to_string(),
+                        start_line: symbol.location.start_line,
+                        end_line: symbol.location.end_line,
+                        lines: candidates[m].lines,
+                    }
+                })
+                .collect();
+            members.sort_by(|a, b| a.path.cmp(&b.path).then(a.start_line.cmp(&b.start_line)));
+
+            DuplicateCluster {
+                size: members.len(),
+                similarity: mean(|s| s.0),
+                embedding_similarity: mean(|s| s.1),
+                consolidatable_lines: total - largest,
+                members,
+            }
+        })
+        .collect();
+
+    clusters.sort_by(|a, b| {
+        b.consolidatable_lines
+            .cmp(&a.consolidatable_lines)
+            .then(b.similarity.total_cmp(&a.similarity))
+    });
+    let cluster_count = clusters.len();
+    clusters.truncate(options.max_clusters);
+
+    Ok(DuplicateReport {
+        candidates: candidates.len(),
+        cluster_count,
+        clusters,
+    })
+}
@@ -0,0 +1,215 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Discovery of where execution starts: mains, handlers, jobs, library roots and tests.
+
+use adi_core::{Symbol, SymbolId, SymbolKind, Visibility};
+use globset::GlobSet;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::filter::is_test_path;
+use crate::source::SourceFile;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    Main,
+    CliCommand,
+    HttpRoute,
+    ScheduledJob,
+    LibraryRoot,
+    Test,
+    /// Non-private callable nothing in the index calls
+    Uncalled,
+}
+
+impl EntryKind {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "main" => Some(EntryKind::Main),
+            "cli_command" => Some(EntryKind::CliCommand),
+            "http_route" => Some(EntryKind::HttpRoute),
+            "scheduled_job" => Some(EntryKind::ScheduledJob),
+            "library_root" => Some(EntryKind::LibraryRoot),
+            "test" => Some(EntryKind::Test),
+            "uncalled" => Some(EntryKind::Uncalled),
+            _ => None,
+        }
+    }
+}
+
+/// Attribute and decorator markers, matched against lowercased annotation lines.
+const TEST_MARKERS: &[&str] = &["test]", "#[rstest", "@test", "@pytest", "@parameterized"];
+const HTTP_MARKERS: &[&str] = &[
+    "#[get(", "#[post(", "#[put(", "#[delete(", "#[patch(", "#[route(", "@getmapping", "@postmapping", "@putmapping",
+    "@deletemapping", "@patchmapping", "@requestmapping", "@get(", "@post(", "@put(", "@delete(", "@patch(",
+];
+/// Route registration on an app or router object: `@app.get(...)`, `@router.post(...)`
+const HTTP_METHOD_CALLS: &[&str] = &[".get(", ".post(", ".put(", ".delete(", ".patch(", ".route(", ".api_route(", ".websocket("];
+const SCHEDULE_MARKERS: &[&str] = &["@scheduled", "@cron", "#[cron", "@shared_task", "@periodic_task", ".task(", ".task\n", "@celery", "schedule.every"];
+const CLI_MARKERS: &[&str] = &["@click.command", "@click.group", ".command(", ".command\n", "@command", "#[command", "@typer"];
+
+const LIBRARY_ROOTS: &[&str] = &["lib.rs", "__init__.py", "index.js", "index.ts", "index.mjs", "index.tsx"];
+
+/// Whether a file is the public root of a library for its language.
+pub fn is_library_root(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| LIBRARY_ROOTS.contains(&n))
+}
+
+/// Attribute, decorator and comment lines directly above a declaration.
+///
+/// Also takes leading decorators inside the symbol's own span, for parsers
+/// that include them in the definition.
+pub fn annotations<'t>(lines: &[&'t str], start_line: usize) -> Vec<&'t str> {
+    let start = start_line.saturating_sub(1).min(lines.len());
+    let is_annotation = |line: &str| line.starts_with('#') || line.starts_with('@') || line.starts_with("//");
+
+    let mut found: Vec<&str> = lines[..start].iter().rev().map(|l| l.trim()).take_while(|l| is_annotation(l)).collect();
+    found.reverse();
+    found.extend(lines[start..].iter().map(|l| l.trim()).take_while(|l| l.starts_with('@') || l.starts_with("#[")));
+    found
+}
+
+/// Classifies a symbol by its annotations and naming conventions.
+///
+/// Returns the kind and the evidence it was recognised by. Callers decide
+/// about [`EntryKind::Uncalled`], which needs the call graph.
+pub fn classify(symbol: &Symbol, annotations: &[&str]) -> Option<(EntryKind, String)> {
+    let callable = matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method);
+    if !callable {
+        let library_root = symbol.parent_id.is_none() && symbol.visibility == Visibility::Public && is_library_root(&symbol.file_path);
+        return library_root.then(|| (EntryKind::LibraryRoot, "public item in library root".to_string()));
+    }
+
+    let marked = |markers: &[&str]| {
+        annotations.iter().find(|line| {
+            let line = format!("{}\n", line.to_lowercase());
+            markers.iter().any(|m| line.contains(m))
+        })
+    };
+    let decorator_call = |calls: &[&str]| {
+        annotations
+            .iter()
+            .find(|line| line.starts_with('@') && calls.iter().any(|c| line.to_lowercase().contains(c)))
+    };
+
+    if symbol.is_entry_point || symbol.name == "main" || symbol.name == "__main__" {
+        return Some((EntryKind::Main, "main function".to_string()));
+    }
+    if let Some(line) = marked(TEST_MARKERS) {
+        return Some((EntryKind::Test, line.to_string()));
+    }
+    if symbol.name.starts_with("test_") || (is_test_path(&symbol.file_path) && symbol.name.starts_with("test")) {
+        return Some((EntryKind::Test, "test naming convention".to_string()));
+    }
+    if let Some(line) = marked(HTTP_MARKERS).or_else(|| decorator_call(HTTP_METHOD_CALLS)) {
+        return Some((EntryKind::HttpRoute, line.to_string()));
+    }
+    if let Some(line) = marked(SCHEDULE_MARKERS) {
+        return Some((EntryKind::ScheduledJob, line.to_string()));
+    }
+    if symbol.name.ends_with("_job") || symbol.name.ends_with("_cron") {
+        return Some((EntryKind::ScheduledJob, "job naming convention".to_string()));
+    }
+    if let Some(line) = marked(CLI_MARKERS) {
+        return Some((EntryKind::CliCommand, line.to_string()));
+    }
+    let in_commands_dir = symbol
+        .file_path
+        .components()
+        .any(|c| matches!(c.as_os_str().to_str(), Some("commands" | "cmd" | "subcommands")));
+    if symbol.name.starts_with("cmd_") || (in_commands_dir && symbol.parent_id.is_none() && symbol.visibility == Visibility::Public) {
+        return Some((EntryKind::CliCommand, "command naming convention".to_string()));
+    }
+    if symbol.parent_id.is_none() && symbol.visibility == Visibility::Public && is_library_root(&symbol.file_path) {
+        return Some((EntryKind::LibraryRoot, "public item in library root".to_string()));
+    }
+    None
+}
+
+pub struct EntryPointOptions {
+    /// Kinds to report; empty for all
+    pub kinds: BTreeSet<EntryKind>,
+    pub path_glob: Option<GlobSet>,
+    pub max_per_kind: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EntryPoint {
+    pub id: SymbolId,
+    pub name: String,
+    pub kind: String,
+    pub path: String,
+    pub line: u32,
+    pub evidence: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EntryGroup {
+    pub kind: EntryKind,
+    pub count: usize,
+    pub files: Vec<String>,
+    pub entries: Vec<EntryPoint>,
+}
+
+/// Entry points across the index, grouped by kind.
+pub fn entry_points(adi: &adi_core::Adi, options: &EntryPointOptions) -> anyhow::Result<Vec<EntryGroup>> {
+    let wanted = |kind: &EntryKind| options.kinds.is_empty() || options.kinds.contains(kind);
+    let tree = adi.get_tree()?;
+    let mut found: BTreeMap<EntryKind, Vec<EntryPoint>> = BTreeMap::new();
+
+    for file in &tree.files {
+        if options.path_glob.as_ref().is_some_and(|g| !g.is_match(&file.path)) {
+            continue;
+        }
+        let Ok(info) = adi.get_file(&file.path) else {
+            continue;
+        };
+        let source = SourceFile::read(adi.project_path(), &file.path).ok();
+        let lines: Vec<&str> = source.as_ref().map(|s| s.text.lines().collect()).unwrap_or_default();
+
+        for symbol in &info.symbols {
+            let annotations = annotations(&lines, symbol.location.start_line as usize);
+            let classified = classify(symbol, &annotations).or_else(|| {
+                let uncalled = wanted(&EntryKind::Uncalled)
+                    && matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method)
+                    && symbol.visibility != Visibility::Private
+                    && !is_test_path(&symbol.file_path)
+                    && adi.get_callers(symbol.id).is_ok_and(|c| c.is_empty());
+                uncalled.then(|| (EntryKind::Uncalled, "no callers in the index".to_string()))
+            });
+            let Some((kind, evidence)) = classified.filter(|(kind, _)| wanted(kind)) else {
+                continue;
+            };
+
+            found.entry(kind).or_default().push(EntryPoint {
+                id: symbol.id,
+                name: symbol.name.clone(),
+                kind: symbol.kind.as_str().to_string(),
+                path: file.path.to_string_lossy().to_string(),
+                line: symbol.location.start_line,
+                evidence,
+            });
+        }
+    }
+
+    Ok(found
+        .into_iter()
+        .map(|(kind, mut entries)| {
+            let files: BTreeSet<String> = entries.iter().map(|e| e.path.clone()).collect();
+            let count = entries.len();
+            entries.truncate(options.max_per_kind);
+            EntryGroup {
+                kind,
+                count,
+                files: files.into_iter().collect(),
+                entries,
+            }
+        })
+        .collect())
+}
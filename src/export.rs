@@ -0,0 +1,287 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Renders call subgraphs as Graphviz DOT, Mermaid flowcharts and GraphML.
+
+use adi_core::SymbolId;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::graph::{self, CallGraph, Direction, GraphNode, Subgraph};
+use crate::{resolve, to_rpc_error, JsonRpcError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Dot,
+    Mermaid,
+    GraphMl,
+}
+
+impl ExportFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "dot" | "graphviz" => Some(ExportFormat::Dot),
+            "mermaid" => Some(ExportFormat::Mermaid),
+            "graphml" => Some(ExportFormat::GraphMl),
+            _ => None,
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            ExportFormat::Dot => "text/vnd.graphviz",
+            ExportFormat::Mermaid => "text/vnd.mermaid",
+            ExportFormat::GraphMl => "application/graphml+xml",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Clustering {
+    None,
+    File,
+    /// Group by the directory containing the file
+    Module,
+}
+
+impl Clustering {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "none" => Some(Clustering::None),
+            "file" => Some(Clustering::File),
+            "module" | "directory" => Some(Clustering::Module),
+            _ => None,
+        }
+    }
+
+    fn key(self, node: &GraphNode) -> Option<String> {
+        match self {
+            Clustering::None => None,
+            Clustering::File => Some(node.path.clone()),
+            Clustering::Module => Some(
+                Path::new(&node.path)
+                    .parent()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .filter(|p| !p.is_empty())
+                    .unwrap_or_else(|| ".".to_string()),
+            ),
+        }
+    }
+}
+
+/// Builds and renders the ego graph described by tool arguments.
+///
+/// Exactly one of `symbol`, `file` or `directory` selects the seeds.
+pub fn export(adi: &adi_core::Adi, arguments: &Value) -> Result<(String, ExportFormat), JsonRpcError> {
+    let invalid = |message: String| JsonRpcError {
+        code: -32602,
+        message,
+        data: None,
+    };
+    let str_arg = |key: &str| arguments.get(key).and_then(|v| v.as_str());
+
+    let format = str_arg("format").unwrap_or("mermaid");
+    let format = ExportFormat::parse(format)
+        .ok_or_else(|| invalid(format!("Invalid format: {} (expected 'dot', 'mermaid' or 'graphml')", format)))?;
+    let cluster = str_arg("cluster").unwrap_or("file");
+    let clustering = Clustering::parse(cluster)
+        .ok_or_else(|| invalid(format!("Invalid cluster: {} (expected 'none', 'file' or 'module')", cluster)))?;
+    let direction = match str_arg("direction").unwrap_or("both") {
+        "both" => None,
+        other => Some(
+            Direction::parse(other)
+                .ok_or_else(|| invalid(format!("Invalid direction: {} (expected 'callers', 'callees' or 'both')", other)))?,
+        ),
+    };
+    let depth = (arguments.get("depth").and_then(|v| v.as_u64()).unwrap_or(2) as usize).clamp(1, 5);
+    let max_edges = (arguments.get("max_edges").and_then(|v| v.as_u64()).unwrap_or(200) as usize).clamp(1, 2000);
+
+    let seeds: Vec<SymbolId> = if arguments.get("symbol").is_some() {
+        vec![resolve::symbol_id(adi, arguments, "symbol")?]
+    } else if let Some(path) = str_arg("file") {
+        let file_info = adi.get_file(Path::new(path)).map_err(to_rpc_error)?;
+        file_info.symbols.iter().map(|s| s.id).collect()
+    } else if let Some(dir) = str_arg("directory") {
+        let dir = Path::new(dir.trim_matches('/'));
+        let tree = adi.get_tree().map_err(to_rpc_error)?;
+        let mut seeds = Vec::new();
+        for file in tree.files.iter().filter(|f| dir.as_os_str().is_empty() || dir == Path::new(".") || f.path.starts_with(dir)) {
+            graph::collect_ids(&file.symbols, &mut seeds);
+        }
+        if seeds.is_empty() {
+            return Err(invalid(format!("No indexed symbols under directory: {}", dir.display())));
+        }
+        seeds
+    } else {
+        return Err(invalid("One of 'symbol', 'file' or 'directory' is required".to_string()));
+    };
+
+    let mut call_graph = CallGraph::new(adi);
+    let subgraph = graph::ego_graph(&mut call_graph, &seeds, direction, depth, max_edges);
+    Ok((render(&subgraph, format, clustering), format))
+}
+
+pub fn render(subgraph: &Subgraph, format: ExportFormat, clustering: Clustering) -> String {
+    match format {
+        ExportFormat::Dot => render_dot(subgraph, clustering),
+        ExportFormat::Mermaid => render_mermaid(subgraph, clustering),
+        ExportFormat::GraphMl => render_graphml(subgraph, clustering),
+    }
+}
+
+/// Nodes grouped by cluster key, in first-seen order within each cluster.
+fn clusters(subgraph: &Subgraph, clustering: Clustering) -> BTreeMap<Option<String>, Vec<&GraphNode>> {
+    let mut clusters: BTreeMap<Option<String>, Vec<&GraphNode>> = BTreeMap::new();
+    for node in &subgraph.nodes {
+        clusters.entry(clustering.key(node)).or_default().push(node);
+    }
+    clusters
+}
+
+fn node_id(id: SymbolId) -> String {
+    format!("n{}", id.0).replace('-', "_")
+}
+
+fn render_dot(subgraph: &Subgraph, clustering: Clustering) -> String {
+    let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut out = String::from("digraph calls {\n  rankdir=LR;\n  node [shape=box, fontname=\"monospace\"];\n");
+    if subgraph.truncated {
+        out.push_str("  // truncated: edge limit reached\n");
+    }
+
+    for (index, (cluster, nodes)) in clusters(subgraph, clustering).into_iter().enumerate() {
+        let indent = if cluster.is_some() { "    " } else { "  " };
+        if let Some(cluster) = &cluster {
+            let _ = writeln!(out, "  subgraph cluster_{} {{\n    label=\"{}\";", index, escape(cluster));
+        }
+        for node in nodes {
+            let style = if subgraph.seeds.contains(&node.id) { ", style=bold" } else { "" };
+            let _ = writeln!(
+                out,
+                "{}{} [label=\"{}\", tooltip=\"{}:{}\"{}];",
+                indent,
+                node_id(node.id),
+                escape(&node.name),
+                escape(&node.path),
+                node.line,
+                style
+            );
+        }
+        if cluster.is_some() {
+            out.push_str("  }\n");
+        }
+    }
+
+    for (from, to) in &subgraph.edges {
+        let _ = writeln!(out, "  {} -> {};", node_id(*from), node_id(*to));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn render_mermaid(subgraph: &Subgraph, clustering: Clustering) -> String {
+    let escape = |s: &str| s.replace('"', "#quot;");
+    let mut out = String::from("flowchart LR\n");
+    if subgraph.truncated {
+        out.push_str("  %% truncated: edge limit reached\n");
+    }
+
+    for (index, (cluster, nodes)) in clusters(subgraph, clustering).into_iter().enumerate() {
+        let indent = if cluster.is_some() { "    " } else { "  " };
+        if let Some(cluster) = &cluster {
+            let _ = writeln!(out, "  subgraph c{}[\"{}\"]", index, escape(cluster));
+        }
+        for node in nodes {
+            let _ = writeln!(out, "{}{}[\"{}\"]", indent, node_id(node.id), escape(&node.name));
+        }
+        if cluster.is_some() {
+            out.push_str("  end\n");
+        }
+    }
+
+    for (from, to) in &subgraph.edges {
+        let _ = writeln!(out, "  {} --> {}", node_id(*from), node_id(*to));
+    }
+    let seeds: Vec<String> = subgraph
+        .nodes
+        .iter()
+        .filter(|n| subgraph.seeds.contains(&n.id))
+        .map(|n| node_id(n.id))
+        .collect();
+    if !seeds.is_empty() {
+        let _ = writeln!(out, "  classDef seed stroke-width:3px\n  class {} seed", seeds.join(","));
+    }
+    out
+}
+
+fn render_graphml(subgraph: &Subgraph, clustering: Clustering) -> String {
+    let escape = |s: &str| {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    };
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n",
+        "  <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>\n",
+        "  <key id=\"path\" for=\"node\" attr.name=\"path\" attr.type=\"string\"/>\n",
+        "  <key id=\"line\" for=\"node\" attr.name=\"line\" attr.type=\"int\"/>\n",
+        "  <key id=\"cluster\" for=\"node\" attr.name=\"cluster\" attr.type=\"string\"/>\n",
+        "  <key id=\"seed\" for=\"node\" attr.name=\"seed\" attr.type=\"boolean\"/>\n",
+    ));
+    if subgraph.truncated {
+        out.push_str("  <!-- truncated: edge limit reached -->\n");
+    }
+    out.push_str("  <graph id=\"calls\" edgedefault=\"directed\">\n");
+
+    for node in &subgraph.nodes {
+        let _ = writeln!(out, "    <node id=\"{}\">", node_id(node.id));
+        let _ = writeln!(out, "      <data key=\"name\">{}</data>", escape(&node.name));
+        let _ = writeln!(out, "      <data key=\"kind\">{}</data>", escape(&node.kind));
+        let _ = writeln!(out, "      <data key=\"path\">{}</data>", escape(&node.path));
+        let _ = writeln!(out, "      <data key=\"line\">{}</data>", node.line);
+        if let Some(cluster) = clustering.key(node) {
+            let _ = writeln!(out, "      <data key=\"cluster\">{}</data>", escape(&cluster));
+        }
+        let _ = writeln!(out, "      <data key=\"seed\">{}</data>", subgraph.seeds.contains(&node.id));
+        out.push_str("    </node>\n");
+    }
+    for (index, (from, to)) in subgraph.edges.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "    <edge id=\"e{}\" source=\"{}\" target=\"{}\"/>",
+            index,
+            node_id(*from),
+            node_id(*to)
+        );
+    }
+
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+/// Tool arguments from an `adi://callgraph/{format}/{scope}/{target}?key=value` resource path.
+///
+/// `rest` is everything after `adi://callgraph/`. Scope is `symbol`, `file` or `dir`.
+pub fn arguments_from_uri(rest: &str) -> Option<Value> {
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let mut parts = path.splitn(3, '/');
+    let format = parts.next().filter(|f| !f.is_empty())?;
+    let scope = match parts.next()? {
+        "symbol" => "symbol",
+        "file" => "file",
+        "dir" | "directory" => "directory",
+        _ => return None,
+    };
+    let target = crate::percent_decode(parts.next().unwrap_or(""));
+
+    let mut arguments = crate::query_arguments(query);
+    arguments.insert("format".to_string(), Value::from(format));
+    arguments.insert(scope.to_string(), Value::from(target));
+    Some(Value::Object(arguments))
+}
@@ -0,0 +1,300 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Language, kind, path and visibility filters for the search tools.
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use crate::query::{self, QueryTerm};
+use crate::JsonRpcError;
+
+/// Upper bound on how many raw results a filtered search may fetch.
+const MAX_FETCH: usize = 1000;
+
+#[derive(Default)]
+pub struct SearchFilter {
+    pub languages: Vec<String>,
+    pub exclude_languages: Vec<String>,
+    pub kinds: Vec<String>,
+    pub exclude_kinds: Vec<String>,
+    pub visibility: Vec<String>,
+    pub exclude_visibility: Vec<String>,
+    pub path_glob: Option<GlobSet>,
+    pub exclude_glob: Option<GlobSet>,
+    /// Case-insensitive globs over symbol names
+    pub name_glob: Option<GlobSet>,
+    pub exclude_name_glob: Option<GlobSet>,
+    pub exclude_tests: bool,
+    /// Language of each indexed file, needed to filter symbols by language
+    file_languages: HashMap<PathBuf, String>,
+}
+
+impl SearchFilter {
+    pub fn from_arguments(arguments: &Value) -> Result<Self, JsonRpcError> {
+        Self::build(arguments, &[])
+    }
+
+    /// Parses the structured syntax of the `query` argument into filters.
+    ///
+    /// Returns the free text to search for along with the combined filter.
+    pub fn from_query(arguments: &Value) -> Result<(String, Self), JsonRpcError> {
+        let raw = arguments.get("query").and_then(|v| v.as_str()).unwrap_or("");
+        let parsed = query::parse(raw).map_err(|e| JsonRpcError {
+            code: -32602,
+            message: e.to_string(),
+            data: Some(json!({ "position": e.position })),
+        })?;
+        Ok((parsed.search_text(), Self::build(arguments, &parsed.terms)?))
+    }
+
+    fn build(arguments: &Value, terms: &[QueryTerm]) -> Result<Self, JsonRpcError> {
+        let term_values = |key: &str, negated: bool| -> Vec<String> {
+            terms
+                .iter()
+                .filter(|t| t.key == key && t.negated == negated)
+                .map(|t| t.value.clone())
+                .collect()
+        };
+        let term_paths = |negated: bool| -> Vec<String> {
+            term_values("path", negated).iter().flat_map(|p| path_patterns(p)).collect()
+        };
+
+        let mut languages = string_list(arguments.get("language"));
+        languages.extend(term_values("lang", false));
+        let mut kinds = string_list(arguments.get("kind"));
+        kinds.extend(term_values("kind", false));
+        let mut visibility = string_list(arguments.get("visibility"));
+        visibility.extend(term_values("vis", false));
+        let mut path_globs = string_list(arguments.get("path_glob"));
+        path_globs.extend(term_paths(false));
+        let mut exclude_globs = string_list(arguments.get("exclude_glob"));
+        exclude_globs.extend(term_paths(true));
+
+        Ok(Self {
+            languages,
+            exclude_languages: term_values("lang", true),
+            kinds,
+            exclude_kinds: term_values("kind", true),
+            visibility,
+            exclude_visibility: term_values("vis", true),
+            path_glob: glob_set(&path_globs, "path_glob", false)?,
+            exclude_glob: glob_set(&exclude_globs, "exclude_glob", false)?,
+            name_glob: glob_set(&term_values("name", false), "name", true)?,
+            exclude_name_glob: glob_set(&term_values("name", true), "name", true)?,
+            exclude_tests: arguments.get("exclude_tests").and_then(|v| v.as_bool()).unwrap_or(false),
+            file_languages: HashMap::new(),
+        })
+    }
+
+    /// Loads file languages from the index when symbols are filtered by language.
+    pub fn load_languages(&mut self, adi: &adi_core::Adi) {
+        if (self.languages.is_empty() && self.exclude_languages.is_empty()) || !self.file_languages.is_empty() {
+            return;
+        }
+        if let Ok(tree) = adi.get_tree() {
+            self.file_languages = tree
+                .files
+                .iter()
+                .map(|f| (f.path.clone(), f.language.as_str().to_string()))
+                .collect();
+        }
+    }
+
+    pub fn matches_path(&self, path: &Path) -> bool {
+        if let Some(include) = &self.path_glob {
+            if !include.is_match(path) {
+                return false;
+            }
+        }
+        if let Some(exclude) = &self.exclude_glob {
+            if exclude.is_match(path) {
+                return false;
+            }
+        }
+        !(self.exclude_tests && is_test_path(path))
+    }
+
+    pub fn matches_language(&self, language: &str) -> bool {
+        (self.languages.is_empty() || contains_ignore_case(&self.languages, language))
+            && !contains_ignore_case(&self.exclude_languages, language)
+    }
+
+    pub fn matches_file(&self, file: &adi_core::File) -> bool {
+        self.matches_language(file.language.as_str()) && self.matches_path(&file.path)
+    }
+
+    pub fn matches_symbol(&self, symbol: &adi_core::Symbol) -> bool {
+        let kind = symbol.kind.as_str();
+        if (!self.kinds.is_empty() && !contains_ignore_case(&self.kinds, kind))
+            || contains_ignore_case(&self.exclude_kinds, kind)
+        {
+            return false;
+        }
+        if !self.visibility.is_empty() || !self.exclude_visibility.is_empty() {
+            let visibility = json!(symbol.visibility);
+            let visibility = visibility.as_str().unwrap_or_default();
+            if (!self.visibility.is_empty() && !contains_ignore_case(&self.visibility, visibility))
+                || contains_ignore_case(&self.exclude_visibility, visibility)
+            {
+                return false;
+            }
+        }
+        if let Some(names) = &self.name_glob {
+            if !names.is_match(&symbol.name) {
+                return false;
+            }
+        }
+        if let Some(names) = &self.exclude_name_glob {
+            if names.is_match(&symbol.name) {
+                return false;
+            }
+        }
+        if !self.languages.is_empty() || !self.exclude_languages.is_empty() {
+            match self.file_languages.get(&symbol.file_path) {
+                Some(language) if self.matches_language(language) => {}
+                _ => return false,
+            }
+        }
+        self.matches_path(&symbol.file_path)
+    }
+}
+
+fn contains_ignore_case(values: &[String], value: &str) -> bool {
+    values.iter().any(|v| v.eq_ignore_ascii_case(value))
+}
+
+/// Expands a query `path:` value into globs; bare names match that file or
+/// directory anywhere in the tree.
+fn path_patterns(value: &str) -> Vec<String> {
+    if value.contains(['*', '?', '[', '{']) {
+        return vec![value.to_string()];
+    }
+    let value = value.trim_end_matches('/');
+    vec![format!("**/{}", value), format!("**/{}/**", value)]
+}
+
+/// Heuristic test-file detection by path conventions across languages.
+pub fn is_test_path(path: &Path) -> bool {
+    let in_test_dir = path.components().any(|c| {
+        matches!(
+            c.as_os_str().to_str(),
+            Some("test" | "tests" | "__tests__" | "spec" | "testdata" | "test_data")
+        )
+    });
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let stem = file_name.split('.').next().unwrap_or("");
+
+    in_test_dir
+        || stem.starts_with("test_")
+        || stem.ends_with("_test")
+        || stem.ends_with("_tests")
+        || stem.ends_with("Test")
+        || stem.ends_with("Tests")
+        || file_name.contains(".test.")
+        || file_name.contains(".spec.")
+        || file_name == "tests.rs"
+}
+
+/// Runs `fetch` with a growing limit until `limit` results pass `keep` or the
+/// source is exhausted.
+pub async fn fetch_filtered<T, E, F, Fut>(
+    limit: usize,
+    mut fetch: F,
+    keep: impl Fn(&T) -> bool,
+) -> Result<Vec<T>, E>
+where
+    F: FnMut(usize) -> Fut,
+    Fut: Future<Output = Result<Vec<T>, E>>,
+{
+    let mut fetch_limit = limit.saturating_mul(4).min(MAX_FETCH).max(limit);
+    loop {
+        let results = fetch(fetch_limit).await?;
+        let exhausted = results.len() < fetch_limit || fetch_limit >= MAX_FETCH;
+        let kept: Vec<T> = results.into_iter().filter(|r| keep(r)).take(limit).collect();
+        if kept.len() >= limit || exhausted {
+            return Ok(kept);
+        }
+        fetch_limit = fetch_limit.saturating_mul(4).min(MAX_FETCH);
+    }
+}
+
+/// Adds the filter parameters to a search tool's input schema.
+pub fn with_filter_properties(mut tool: Value, symbols: bool) -> Value {
+    let properties = &mut tool["inputSchema"]["properties"];
+    properties["language"] = json!({
+        "type": ["string", "array"],
+        "items": { "type": "string" },
+        "description": "Only include results in these languages (e.g., 'rust' or ['rust', 'go'])"
+    });
+    properties["path_glob"] = json!({
+        "type": ["string", "array"],
+        "items": { "type": "string" },
+        "description": "Only include files matching these globs (e.g., 'services/**')"
+    });
+    properties["exclude_glob"] = json!({
+        "type": ["string", "array"],
+        "items": { "type": "string" },
+        "description": "Exclude files matching these globs (e.g., '**/*.min.js')"
+    });
+    properties["exclude_tests"] = json!({
+        "type": "boolean",
+        "description": "Exclude test files and test directories",
+        "default": false
+    });
+    if symbols {
+        properties["kind"] = json!({
+            "type": ["string", "array"],
+            "items": { "type": "string" },
+            "description": "Only include these symbol kinds (e.g., 'function', 'struct')"
+        });
+        properties["visibility"] = json!({
+            "type": ["string", "array"],
+            "items": { "type": "string" },
+            "description": "Only include symbols with this visibility (e.g., 'public')"
+        });
+    }
+    tool
+}
+
+/// Reads a glob argument given as a string or an array of strings.
+pub fn glob_argument(arguments: &Value, key: &str) -> Result<Option<GlobSet>, JsonRpcError> {
+    glob_set(&string_list(arguments.get(key)), key, false)
+}
+
+/// Reads an argument given either as a single string or an array of strings.
+pub fn string_list(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::String(s)) => vec![s.clone()],
+        Some(Value::Array(items)) => items.iter().filter_map(|v| v.as_str()).map(String::from).collect(),
+        _ => vec![],
+    }
+}
+
+fn glob_set(patterns: &[String], name: &str, case_insensitive: bool) -> Result<Option<GlobSet>, JsonRpcError> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .build()
+            .map_err(|e| JsonRpcError {
+                code: -32602,
+                message: format!("Invalid {}: {}", name, e),
+                data: None,
+            })?;
+        builder.add(glob);
+    }
+    builder.build().map(Some).map_err(|e| JsonRpcError {
+        code: -32602,
+        message: format!("Invalid {}: {}", name, e),
+        data: None,
+    })
+}
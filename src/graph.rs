@@ -0,0 +1,505 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Call graph traversal over the caller/callee edges of the index.
+
+use adi_core::{Symbol, SymbolId, SymbolNode};
+use globset::GlobSet;
+use serde::Serialize;
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
+use std::path::Path;
+
+use crate::filter::is_test_path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Callers,
+    Callees,
+}
+
+impl Direction {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "callers" | "incoming" => Some(Direction::Callers),
+            "callees" | "outgoing" => Some(Direction::Callees),
+            _ => None,
+        }
+    }
+}
+
+/// Lazily loaded view of the call graph; each symbol's edges are fetched once.
+pub struct CallGraph<'a> {
+    adi: &'a adi_core::Adi,
+    symbols: HashMap<SymbolId, Symbol>,
+    callers: HashMap<SymbolId, Vec<SymbolId>>,
+    callees: HashMap<SymbolId, Vec<SymbolId>>,
+}
+
+impl<'a> CallGraph<'a> {
+    pub fn new(adi: &'a adi_core::Adi) -> Self {
+        Self {
+            adi,
+            symbols: HashMap::new(),
+            callers: HashMap::new(),
+            callees: HashMap::new(),
+        }
+    }
+
+    pub fn symbol(&mut self, id: SymbolId) -> Option<&Symbol> {
+        if !self.symbols.contains_key(&id) {
+            let symbol = self.adi.get_symbol(id).ok()?;
+            self.symbols.insert(id, symbol);
+        }
+        self.symbols.get(&id)
+    }
+
+    /// Registers a symbol already loaded elsewhere, saving a lookup.
+    pub fn insert(&mut self, symbol: Symbol) {
+        self.symbols.entry(symbol.id).or_insert(symbol);
+    }
+
+    /// Distinct neighbours of `id` in the given direction.
+    pub fn neighbours(&mut self, id: SymbolId, direction: Direction) -> Vec<SymbolId> {
+        if !self.callers.contains_key(&id) || !self.callees.contains_key(&id) {
+            self.load(id);
+        }
+        let cache = match direction {
+            Direction::Callers => &self.callers,
+            Direction::Callees => &self.callees,
+        };
+        cache.get(&id).cloned().unwrap_or_default()
+    }
+
+    /// Fetches both edge directions of `id` in a single usage lookup.
+    fn load(&mut self, id: SymbolId) {
+        let (callers, callees) = match self.adi.get_symbol_usage(id) {
+            Ok(usage) => {
+                self.insert(usage.symbol);
+                (usage.callers, usage.callees)
+            }
+            Err(_) => (vec![], vec![]),
+        };
+        let caller_ids = callers.iter().map(|s| s.id).collect();
+        let callee_ids = callees.iter().map(|s| s.id).collect();
+        callers.into_iter().chain(callees).for_each(|s| self.insert(s));
+        self.insert_edges(id, Direction::Callers, caller_ids);
+        self.insert_edges(id, Direction::Callees, callee_ids);
+    }
+
+    /// Records the edges of `id`, dropping duplicates.
+    pub fn insert_edges(&mut self, id: SymbolId, direction: Direction, ids: Vec<SymbolId>) {
+        let mut seen = HashSet::new();
+        let ids: Vec<SymbolId> = ids.into_iter().filter(|i| seen.insert(*i)).collect();
+        match direction {
+            Direction::Callers => self.callers.insert(id, ids),
+            Direction::Callees => self.callees.insert(id, ids),
+        };
+    }
+
+    pub fn node(&mut self, id: SymbolId) -> GraphNode {
+        match self.symbol(id) {
+            Some(symbol) => GraphNode::from(symbol),
+            None => GraphNode {
+                id,
+                name: format!("#{}", id.0),
+                kind: "unknown".to_string(),
+                path: String::new(),
+                line: 0,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphNode {
+    pub id: SymbolId,
+    pub name: String,
+    pub kind: String,
+    pub path: String,
+    pub line: u32,
+}
+
+impl From<&Symbol> for GraphNode {
+    fn from(symbol: &Symbol) -> Self {
+        Self {
+            id: symbol.id,
+            name: symbol.name.clone(),
+            kind: symbol.kind.as_str().to_string(),
+            path: symbol.file_path.to_string_lossy().to_string(),
+            line: symbol.location.start_line,
+        }
+    }
+}
+
+/// Heuristic check for code outside the project's own sources.
+pub fn is_external_path(path: &Path) -> bool {
+    path.as_os_str().is_empty()
+        || path.is_absolute()
+        || path.components().any(|c| {
+            matches!(
+                c.as_os_str().to_str(),
+                Some("node_modules" | "vendor" | "third_party" | "site-packages" | ".cargo" | "target")
+            )
+        })
+}
+
+/// Where a traversal stops expanding.
+#[derive(Default)]
+pub struct Boundaries {
+    pub tests: bool,
+    pub external: bool,
+    pub glob: Option<GlobSet>,
+}
+
+impl Boundaries {
+    /// Reason the symbol is a boundary, if it is one.
+    pub fn check(&self, symbol: Option<&Symbol>) -> Option<&'static str> {
+        let path = symbol.map_or(Path::new(""), |s| s.file_path.as_path());
+        if self.external && is_external_path(path) {
+            Some("external")
+        } else if self.tests && is_test_path(path) {
+            Some("test")
+        } else if self.glob.as_ref().is_some_and(|g| g.is_match(path)) {
+            Some("glob")
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct HierarchyNode {
+    #[serde(flatten)]
+    pub node: GraphNode,
+    pub depth: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<HierarchyNode>,
+    /// Node is an ancestor on the current path; not expanded again
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cycle: bool,
+    /// Node was already expanded elsewhere in the tree
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub shared: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boundary: Option<&'static str>,
+    /// Node has further neighbours beyond the depth or node limit
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HierarchyEdge {
+    pub from: SymbolId,
+    pub to: SymbolId,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cycle: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CallHierarchy {
+    pub direction: Direction,
+    pub root: HierarchyNode,
+    pub node_count: usize,
+    pub edges: Vec<HierarchyEdge>,
+    pub truncated: bool,
+}
+
+/// Flat form of a hierarchy: each node once, plus the edge list.
+#[derive(Debug, Serialize)]
+pub struct CallDag {
+    pub direction: Direction,
+    pub nodes: Vec<DagNode>,
+    pub edges: Vec<HierarchyEdge>,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DagNode {
+    #[serde(flatten)]
+    pub node: GraphNode,
+    pub depth: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub boundary: Option<&'static str>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+impl CallHierarchy {
+    pub fn into_dag(self) -> CallDag {
+        fn collect(node: HierarchyNode, nodes: &mut Vec<DagNode>) {
+            if !node.cycle && !node.shared {
+                nodes.push(DagNode {
+                    node: node.node,
+                    depth: node.depth,
+                    boundary: node.boundary,
+                    truncated: node.truncated,
+                });
+            }
+            for child in node.children {
+                collect(child, nodes);
+            }
+        }
+
+        let mut nodes = Vec::new();
+        collect(self.root, &mut nodes);
+        CallDag {
+            direction: self.direction,
+            nodes,
+            edges: self.edges,
+            truncated: self.truncated,
+        }
+    }
+}
+
+pub struct HierarchyOptions {
+    pub direction: Direction,
+    pub max_depth: usize,
+    pub max_nodes: usize,
+    pub boundaries: Boundaries,
+}
+
+/// Walks the call graph depth-first from `root`, expanding each node once
+/// at the shallowest depth it is reachable from `root`.
+pub fn call_hierarchy(graph: &mut CallGraph, root: SymbolId, options: &HierarchyOptions) -> CallHierarchy {
+    let mut walk = HierarchyWalk {
+        options,
+        shallowest: shallowest_depths(graph, root, options),
+        seen: HashSet::new(),
+        expanded: HashSet::new(),
+        path: Vec::new(),
+        edges: Vec::new(),
+        truncated: false,
+    };
+    let root = walk.visit(graph, root, 0);
+    CallHierarchy {
+        direction: options.direction,
+        node_count: walk.seen.len(),
+        root,
+        edges: walk.edges,
+        truncated: walk.truncated,
+    }
+}
+
+/// Breadth-first depth of every node within `max_depth` of `root`, not
+/// walking past boundaries.
+fn shallowest_depths(graph: &mut CallGraph, root: SymbolId, options: &HierarchyOptions) -> HashMap<SymbolId, usize> {
+    let mut depths = HashMap::from([(root, 0)]);
+    let mut queue = VecDeque::from([(root, 0)]);
+    while let Some((id, depth)) = queue.pop_front() {
+        if depth >= options.max_depth {
+            continue;
+        }
+        if depth > 0 && options.boundaries.check(graph.symbol(id)).is_some() {
+            continue;
+        }
+        for next in graph.neighbours(id, options.direction) {
+            if let Entry::Vacant(entry) = depths.entry(next) {
+                entry.insert(depth + 1);
+                queue.push_back((next, depth + 1));
+            }
+        }
+    }
+    depths
+}
+
+struct HierarchyWalk<'o> {
+    options: &'o HierarchyOptions,
+    /// Depth at which each node is expanded; deeper occurrences are `shared`
+    shallowest: HashMap<SymbolId, usize>,
+    seen: HashSet<SymbolId>,
+    expanded: HashSet<SymbolId>,
+    path: Vec<SymbolId>,
+    edges: Vec<HierarchyEdge>,
+    truncated: bool,
+}
+
+impl HierarchyWalk<'_> {
+    fn visit(&mut self, graph: &mut CallGraph, id: SymbolId, depth: usize) -> HierarchyNode {
+        let mut node = HierarchyNode {
+            node: graph.node(id),
+            depth,
+            children: Vec::new(),
+            cycle: false,
+            shared: false,
+            boundary: None,
+            truncated: false,
+        };
+        self.seen.insert(id);
+
+        if self.path.contains(&id) {
+            node.cycle = true;
+            return node;
+        }
+        let deeper = self.shallowest.get(&id).is_some_and(|shallowest| depth > *shallowest);
+        if deeper || self.expanded.contains(&id) {
+            node.shared = true;
+            return node;
+        }
+        if depth > 0 {
+            node.boundary = self.options.boundaries.check(graph.symbol(id));
+            if node.boundary.is_some() {
+                return node;
+            }
+        }
+
+        let neighbours = graph.neighbours(id, self.options.direction);
+        if neighbours.is_empty() {
+            return node;
+        }
+        if depth >= self.options.max_depth {
+            node.truncated = true;
+            self.truncated = true;
+            return node;
+        }
+
+        self.expanded.insert(id);
+        self.path.push(id);
+        for next in neighbours {
+            if self.seen.len() >= self.options.max_nodes && !self.seen.contains(&next) {
+                node.truncated = true;
+                self.truncated = true;
+                break;
+            }
+            let (from, to) = match self.options.direction {
+                Direction::Callees => (id, next),
+                Direction::Callers => (next, id),
+            };
+            self.edges.push(HierarchyEdge {
+                from,
+                to,
+                cycle: self.path.contains(&next),
+            });
+            node.children.push(self.visit(graph, next, depth + 1));
+        }
+        self.path.pop();
+
+        node
+    }
+}
+
+pub struct CallPathOptions {
+    /// Number of shortest paths to return
+    pub k: usize,
+    pub max_depth: usize,
+    /// Cap on partial paths expanded, bounding work on dense graphs
+    pub max_expansions: usize,
+}
+
+/// Finds up to `k` shortest simple call paths from `from` to `to` along callee edges.
+///
+/// Paths are explored breadth-first, so they come out shortest first. Each
+/// node is expanded at most `k` times, which is enough to reach the `k`
+/// shortest paths without enumerating every walk. When `from == to` the
+/// paths found are call cycles through it.
+pub fn call_paths(graph: &mut CallGraph, from: SymbolId, to: SymbolId, options: &CallPathOptions) -> Vec<Vec<SymbolId>> {
+    let mut paths = Vec::new();
+    let mut expanded: HashMap<SymbolId, usize> = HashMap::new();
+    let mut queue = VecDeque::from([vec![from]]);
+    let mut expansions = 0;
+
+    while let Some(path) = queue.pop_front() {
+        let last = path[path.len() - 1];
+        if last == to && path.len() > 1 {
+            paths.push(path);
+            if paths.len() >= options.k {
+                break;
+            }
+            continue;
+        }
+        if path.len() > options.max_depth || expansions >= options.max_expansions {
+            continue;
+        }
+        let count = expanded.entry(last).or_default();
+        if *count >= options.k {
+            continue;
+        }
+        *count += 1;
+        expansions += 1;
+
+        for next in graph.neighbours(last, Direction::Callees) {
+            if next == to || !path.contains(&next) {
+                let mut extended = path.clone();
+                extended.push(next);
+                queue.push_back(extended);
+            }
+        }
+    }
+
+    paths
+}
+
+/// A bounded slice of the call graph around a set of seed symbols.
+#[derive(Debug, Default)]
+pub struct Subgraph {
+    pub nodes: Vec<GraphNode>,
+    pub seeds: HashSet<SymbolId>,
+    pub edges: Vec<(SymbolId, SymbolId)>,
+    pub truncated: bool,
+}
+
+/// Collects the neighbourhood of `seeds` up to `depth` hops.
+///
+/// `direction` of `None` follows both callers and callees. Stops adding edges
+/// once `max_edges` is reached and marks the result truncated.
+pub fn ego_graph(
+    graph: &mut CallGraph,
+    seeds: &[SymbolId],
+    direction: Option<Direction>,
+    depth: usize,
+    max_edges: usize,
+) -> Subgraph {
+    let directions = match direction {
+        Some(direction) => vec![direction],
+        None => vec![Direction::Callees, Direction::Callers],
+    };
+    let mut subgraph = Subgraph {
+        seeds: seeds.iter().copied().collect(),
+        ..Default::default()
+    };
+    let mut included: Vec<SymbolId> = Vec::new();
+    let mut seen_nodes = HashSet::new();
+    let mut seen_edges = HashSet::new();
+    let mut frontier: Vec<SymbolId> = seeds.iter().copied().filter(|id| seen_nodes.insert(*id)).collect();
+    included.extend(&frontier);
+
+    'levels: for _ in 0..depth {
+        let mut next_frontier = Vec::new();
+        for &id in &frontier {
+            for &direction in &directions {
+                for next in graph.neighbours(id, direction) {
+                    let edge = match direction {
+                        Direction::Callees => (id, next),
+                        Direction::Callers => (next, id),
+                    };
+                    if seen_edges.contains(&edge) {
+                        continue;
+                    }
+                    if seen_edges.len() >= max_edges {
+                        subgraph.truncated = true;
+                        break 'levels;
+                    }
+                    seen_edges.insert(edge);
+                    subgraph.edges.push(edge);
+                    if seen_nodes.insert(next) {
+                        included.push(next);
+                        next_frontier.push(next);
+                    }
+                }
+            }
+        }
+        frontier = next_frontier;
+    }
+
+    subgraph.nodes = included.into_iter().map(|id| graph.node(id)).collect();
+    subgraph
+}
+
+/// Ids of all symbols in a tree of symbol nodes, parents before children.
+pub fn collect_ids(symbols: &[SymbolNode], ids: &mut Vec<SymbolId>) {
+    for symbol in symbols {
+        ids.push(symbol.id);
+        collect_ids(&symbol.children, ids);
+    }
+}
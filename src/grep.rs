@@ -0,0 +1,127 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Regex and literal text search over indexed files.
+
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::filter::SearchFilter;
+use crate::navigation::symbol_at;
+use crate::source::SourceFile;
+
+pub struct GrepOptions {
+    pub regex: Regex,
+    pub filter: SearchFilter,
+    pub context: usize,
+    pub max_results: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GrepMatch {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub before: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<String>,
+    /// Innermost indexed symbol containing the match
+    pub symbol: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GrepResult {
+    pub matches: Vec<GrepMatch>,
+    pub files_searched: usize,
+    pub truncated: bool,
+}
+
+/// Builds the search regex, escaping the pattern in literal mode.
+pub fn build_regex(pattern: &str, literal: bool, case_sensitive: bool) -> Result<Regex, regex::Error> {
+    let pattern = if literal { regex::escape(pattern) } else { pattern.to_string() };
+    regex::RegexBuilder::new(&pattern)
+        .case_insensitive(!case_sensitive)
+        .build()
+}
+
+/// Matches within a single file: `(line, column, before, after)`, 1-based.
+pub fn grep_text(text: &str, regex: &Regex, context: usize, limit: usize) -> Vec<(usize, usize, Vec<String>, Vec<String>)> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut hits = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if hits.len() >= limit {
+            break;
+        }
+        if let Some(m) = regex.find(line) {
+            let before = lines[i.saturating_sub(context)..i].iter().map(|l| l.to_string()).collect();
+            let after = lines[i + 1..(i + 1).saturating_add(context).min(lines.len())].iter().map(|l| l.to_string()).collect();
+            hits.push((i + 1, line[..m.start()].chars().count() + 1, before, after));
+        }
+    }
+
+    hits
+}
+
+pub fn grep(adi: &adi_core::Adi, options: &GrepOptions) -> anyhow::Result<GrepResult> {
+    let tree = adi.get_tree()?;
+    let mut matches = Vec::new();
+    let mut files_searched = 0;
+    let mut truncated = false;
+
+    for file_node in &tree.files {
+        if !options.filter.matches_language(file_node.language.as_str()) || !options.filter.matches_path(&file_node.path) {
+            continue;
+        }
+
+        let Ok(file) = SourceFile::read(adi.project_path(), &file_node.path) else {
+            continue;
+        };
+        files_searched += 1;
+
+        let remaining = options.max_results - matches.len();
+        let hits = grep_text(&file.text, &options.regex, options.context, remaining + 1);
+        if hits.is_empty() {
+            continue;
+        }
+
+        let symbols = adi.get_file(&file_node.path).map(|info| info.symbols).unwrap_or_default();
+        let lines: Vec<&str> = file.text.lines().collect();
+
+        for (line, column, before, after) in hits {
+            if matches.len() >= options.max_results {
+                truncated = true;
+                break;
+            }
+            matches.push(GrepMatch {
+                path: file_node.path.to_string_lossy().to_string(),
+                line,
+                column,
+                text: lines[line - 1].to_string(),
+                before,
+                after,
+                symbol: symbol_at(&symbols, line, column).map(|s| {
+                    json!({
+                        "id": s.id,
+                        "name": s.name,
+                        "kind": s.kind.as_str()
+                    })
+                }),
+            });
+        }
+
+        if truncated {
+            break;
+        }
+    }
+
+    Ok(GrepResult {
+        matches,
+        files_searched,
+        truncated,
+    })
+}
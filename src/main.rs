@@ -2,6 +2,9 @@
 // SPDX-License-Identifier: BSL-1.1
 // See LICENSE file for details
 
+// The tools/list schema is one large json! literal
+#![recursion_limit = "256"]
+
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -13,6 +16,31 @@ use tokio::sync::Mutex;
 use tracing::{debug, error, info};
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
+mod batch;
+mod budget;
+mod contextpack;
+mod cycles;
+mod deadcode;
+mod deps;
+mod duplicates;
+mod entrypoints;
+mod export;
+mod filter;
+mod graph;
+mod grep;
+mod navigation;
+mod neighbourhood;
+mod outline;
+mod query;
+mod render;
+mod repomap;
+mod resolve;
+mod search;
+mod similar;
+mod source;
+mod tokens;
+mod tree;
+
 #[cfg(test)]
 mod tests;
 
@@ -89,6 +117,8 @@ pub struct McpServer {
     adi: Option<adi_core::Adi>,
     pub project_path: PathBuf,
     pub subscribed_resources: HashSet<String>,
+    /// Output format for tools called without `format`
+    pub default_format: render::OutputFormat,
 }
 
 impl McpServer {
@@ -97,6 +127,7 @@ impl McpServer {
             adi: None,
             project_path: PathBuf::from("."),
             subscribed_resources: HashSet::new(),
+            default_format: render::OutputFormat::default(),
         }
     }
 
@@ -156,6 +187,13 @@ impl McpServer {
                 let path = root_uri.strip_prefix("file://").unwrap_or(root_uri);
                 self.project_path = PathBuf::from(path);
             }
+            let client = params.pointer("/clientInfo/name").and_then(|v| v.as_str());
+            if let Some(format) = std::env::var(render::FORMAT_ENV)
+                .ok()
+                .and_then(|config| render::OutputFormat::client_default(&config, client))
+            {
+                self.default_format = format;
+            }
         }
 
         match adi_core::Adi::open(&self.project_path).await {
@@ -192,17 +230,17 @@ impl McpServer {
     // ==================== TOOLS ====================
 
     async fn handle_tools_list(&self) -> Result<Value, JsonRpcError> {
-        Ok(json!({
+        let mut result = json!({
             "tools": [
-                {
+                filter::with_filter_properties(json!({
                     "name": "search",
-                    "description": "Semantic search for code symbols using natural language. Returns symbols ranked by relevance.",
+                    "description": "Semantic search for code symbols using natural language. Returns symbols ranked by relevance. Hybrid mode fuses semantic and full-text rankings.",
                     "inputSchema": {
                         "type": "object",
                         "properties": {
                             "query": {
                                 "type": "string",
-                                "description": "Natural language search query (e.g., 'function that handles user authentication')"
+                                "description": "Natural language search query (e.g., 'function that handles user authentication'). Accepts filter syntax: kind:function lang:rust path:src/** name:conn* -path:tests \"exact phrase\""
                             },
                             "limit": {
                                 "type": "integer",
@@ -210,12 +248,25 @@ impl McpServer {
                                 "default": 10,
                                 "minimum": 1,
                                 "maximum": 100
+                            },
+                            "mode": {
+                                "type": "string",
+                                "enum": ["semantic", "hybrid"],
+                                "description": "'semantic' for embedding similarity only, 'hybrid' to fuse with full-text name matching via reciprocal rank fusion",
+                                "default": "semantic"
+                            },
+                            "semantic_weight": {
+                                "type": "number",
+                                "description": "Hybrid mode only: weight of the semantic ranking (0.0-1.0); full-text gets the remainder",
+                                "default": 0.5,
+                                "minimum": 0,
+                                "maximum": 1
                             }
                         },
                         "required": ["query"]
                     }
-                },
-                {
+                }), true),
+                filter::with_filter_properties(json!({
                     "name": "search_symbols",
                     "description": "Full-text search for symbols by name. Use for finding specific functions, classes, or variables.",
                     "inputSchema": {
@@ -223,110 +274,833 @@ impl McpServer {
                         "properties": {
                             "query": {
                                 "type": "string",
-                                "description": "Symbol name to search (supports partial matching)"
+                                "description": "Symbol name to search (supports partial matching). Accepts filter syntax: kind:function lang:rust path:src/** name:conn* -path:tests \"exact phrase\""
                             },
                             "limit": {
                                 "type": "integer",
-                                "default": 10
+                                "description": "Maximum number of results (1-100)",
+                                "default": 10,
+                                "minimum": 1,
+                                "maximum": 100
                             }
                         },
                         "required": ["query"]
                     }
+                }), true),
+                filter::with_filter_properties(json!({
+                    "name": "search_files",
+                    "description": "Full-text search for files by path or name.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "query": {
+                                "type": "string",
+                                "description": "File path or name pattern. Accepts filter syntax: lang:rust path:src/** -path:tests"
+                            },
+                            "limit": {
+                                "type": "integer",
+                                "description": "Maximum number of results (1-100)",
+                                "default": 10,
+                                "minimum": 1,
+                                "maximum": 100
+                            }
+                        },
+                        "required": ["query"]
+                    }
+                }), false),
+                filter::with_filter_properties(json!({
+                    "name": "find_similar",
+                    "description": "Find the symbols most similar to a given symbol by embedding, excluding the symbol itself. Use to find existing helpers before writing new ones.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "id": {
+                                "type": ["integer", "string"],
+                                "description": "Symbol ID (from search results), symbol name, qualified path (crate::module::Type::method), file#name or stable key"
+                            },
+                            "limit": {
+                                "type": "integer",
+                                "description": "Maximum number of similar symbols (1-50)",
+                                "default": 10,
+                                "minimum": 1,
+                                "maximum": 50
+                            },
+                            "min_similarity": {
+                                "type": "number",
+                                "description": "Drop neighbours scoring below this similarity",
+                                "default": 0,
+                                "minimum": 0,
+                                "maximum": 1
+                            }
+                        },
+                        "required": ["id"]
+                    }
+                }), true),
+                filter::with_filter_properties(json!({
+                    "name": "grep",
+                    "description": "Search the text of indexed files with a regex or literal pattern. Each match is tagged with its enclosing symbol (name, kind, id) for use with the graph tools.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "pattern": {
+                                "type": "string",
+                                "description": "Text or regular expression to search for"
+                            },
+                            "regex": {
+                                "type": "boolean",
+                                "description": "Treat the pattern as a regular expression instead of a literal string",
+                                "default": false
+                            },
+                            "case_sensitive": {
+                                "type": "boolean",
+                                "default": true
+                            },
+                            "context": {
+                                "type": "integer",
+                                "description": "Number of context lines before and after each match (0-50)",
+                                "default": 0,
+                                "minimum": 0,
+                                "maximum": 50
+                            },
+                            "max_results": {
+                                "type": "integer",
+                                "description": "Maximum number of matches (1-1000)",
+                                "default": 100,
+                                "minimum": 1,
+                                "maximum": 1000
+                            }
+                        },
+                        "required": ["pattern"]
+                    }
+                }), false),
+                {
+                    "name": "get_symbol",
+                    "description": "Get detailed information about a specific symbol by its ID or name. Ambiguous names return a ranked list of candidates.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "id": {
+                                "type": ["integer", "string"],
+                                "description": "Symbol ID (from search results), symbol name, qualified path (crate::module::Type::method), file#name or stable key"
+                            }
+                        },
+                        "required": ["id"]
+                    }
+                },
+                {
+                    "name": "get_file",
+                    "description": "Get file information including all symbols defined in it.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "path": {
+                                "type": "string",
+                                "description": "File path relative to project root"
+                            }
+                        },
+                        "required": ["path"]
+                    }
+                },
+                {
+                    "name": "get_symbols",
+                    "description": "Batch get_symbol: details for several symbols at once. Results are listed per input; a symbol that cannot be found is reported as an error for that item only.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "ids": {
+                                "type": "array",
+                                "items": { "type": ["integer", "string"] },
+                                "description": "Symbol IDs, names, qualified paths or stable keys (at most 100)"
+                            }
+                        },
+                        "required": ["ids"]
+                    }
+                },
+                {
+                    "name": "get_file_outline",
+                    "description": "Get a hierarchical outline of a file: methods nested under their impl, class or module, each with its line range, signature and doc-comment summary. Read this before deciding which spans to fetch.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "path": {
+                                "type": "string",
+                                "description": "File path relative to project root"
+                            }
+                        },
+                        "required": ["path"]
+                    }
+                },
+                {
+                    "name": "get_files",
+                    "description": "Batch get_file: information and symbols for several files at once, with per-item errors.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "paths": {
+                                "type": "array",
+                                "items": { "type": "string" },
+                                "description": "File paths relative to project root (at most 100)"
+                            }
+                        },
+                        "required": ["paths"]
+                    }
+                },
+                {
+                    "name": "get_symbol_source",
+                    "description": "Get the exact source code of a symbol with line numbers, optionally with surrounding context lines.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "id": {
+                                "type": ["integer", "string"],
+                                "description": "Symbol ID (from search results), symbol name, qualified path (crate::module::Type::method), file#name or stable key"
+                            },
+                            "context_before": {
+                                "type": "integer",
+                                "description": "Number of lines to include before the symbol (0-200)",
+                                "default": 0,
+                                "minimum": 0,
+                                "maximum": 200
+                            },
+                            "context_after": {
+                                "type": "integer",
+                                "description": "Number of lines to include after the symbol (0-200)",
+                                "default": 0,
+                                "minimum": 0,
+                                "maximum": 200
+                            }
+                        },
+                        "required": ["id"]
+                    }
+                },
+                {
+                    "name": "get_symbol_sources",
+                    "description": "Batch get_symbol_source: source spans of several symbols at once, with per-item errors.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "ids": {
+                                "type": "array",
+                                "items": { "type": ["integer", "string"] },
+                                "description": "Symbol IDs, names, qualified paths or stable keys (at most 100)"
+                            },
+                            "context_before": {
+                                "type": "integer",
+                                "description": "Number of lines to include before each symbol (0-200)",
+                                "default": 0,
+                                "minimum": 0,
+                                "maximum": 200
+                            },
+                            "context_after": {
+                                "type": "integer",
+                                "description": "Number of lines to include after each symbol (0-200)",
+                                "default": 0,
+                                "minimum": 0,
+                                "maximum": 200
+                            }
+                        },
+                        "required": ["ids"]
+                    }
+                },
+                {
+                    "name": "read_lines",
+                    "description": "Read a line range of an indexed file with line numbers and a content hash.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "path": {
+                                "type": "string",
+                                "description": "File path relative to project root"
+                            },
+                            "start_line": {
+                                "type": "integer",
+                                "description": "First line to return (1-based)",
+                                "default": 1,
+                                "minimum": 1
+                            },
+                            "end_line": {
+                                "type": "integer",
+                                "description": "Last line to return (inclusive). Defaults to the end of the file."
+                            }
+                        },
+                        "required": ["path"]
+                    }
+                },
+                {
+                    "name": "symbol_at_position",
+                    "description": "Find the innermost symbol enclosing a file position, with its line and column span.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "path": {
+                                "type": "string",
+                                "description": "File path relative to project root"
+                            },
+                            "line": {
+                                "type": "integer",
+                                "description": "Line number (1-based)",
+                                "minimum": 1
+                            },
+                            "column": {
+                                "type": "integer",
+                                "description": "Column number (1-based)",
+                                "minimum": 1
+                            }
+                        },
+                        "required": ["path", "line", "column"]
+                    }
+                },
+                {
+                    "name": "goto_definition",
+                    "description": "Resolve the identifier at a file position to its definition locations.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "path": {
+                                "type": "string",
+                                "description": "File path relative to project root"
+                            },
+                            "line": {
+                                "type": "integer",
+                                "description": "Line number (1-based)",
+                                "minimum": 1
+                            },
+                            "column": {
+                                "type": "integer",
+                                "description": "Column number (1-based)",
+                                "minimum": 1
+                            }
+                        },
+                        "required": ["path", "line", "column"]
+                    }
+                },
+                {
+                    "name": "find_references",
+                    "description": "List references to the symbol at a file position: each caller with the line and column spans where it is used.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "path": {
+                                "type": "string",
+                                "description": "File path relative to project root"
+                            },
+                            "line": {
+                                "type": "integer",
+                                "description": "Line number (1-based)",
+                                "minimum": 1
+                            },
+                            "column": {
+                                "type": "integer",
+                                "description": "Column number (1-based)",
+                                "minimum": 1
+                            }
+                        },
+                        "required": ["path", "line", "column"]
+                    }
+                },
+                {
+                    "name": "get_callers",
+                    "description": "Find all symbols that call/reference a given symbol.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "id": {
+                                "type": ["integer", "string"],
+                                "description": "Symbol ID to find callers for, symbol name, qualified path (crate::module::Type::method), file#name or stable key"
+                            }
+                        },
+                        "required": ["id"]
+                    }
+                },
+                {
+                    "name": "get_callees",
+                    "description": "Find all symbols that a given symbol calls/references.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "id": {
+                                "type": ["integer", "string"],
+                                "description": "Symbol ID to find callees for, symbol name, qualified path (crate::module::Type::method), file#name or stable key"
+                            }
+                        },
+                        "required": ["id"]
+                    }
+                },
+                {
+                    "name": "get_call_hierarchy",
+                    "description": "Walk the call graph transitively from a symbol, in either direction, up to a depth and node limit. Shared nodes are expanded once and cycles are marked.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "id": {
+                                "type": ["integer", "string"],
+                                "description": "Symbol ID, symbol name, qualified path, file#name or stable key"
+                            },
+                            "direction": {
+                                "type": "string",
+                                "enum": ["callers", "callees"],
+                                "description": "'callers' walks up to who calls the symbol, 'callees' walks down to what it calls",
+                                "default": "callees"
+                            },
+                            "max_depth": {
+                                "type": "integer",
+                                "description": "Maximum number of hops (1-10)",
+                                "default": 3,
+                                "minimum": 1,
+                                "maximum": 10
+                            },
+                            "max_nodes": {
+                                "type": "integer",
+                                "description": "Maximum number of distinct symbols to expand (1-1000)",
+                                "default": 100,
+                                "minimum": 1,
+                                "maximum": 1000
+                            },
+                            "stop_at": {
+                                "type": "array",
+                                "items": { "type": "string", "enum": ["tests", "external"] },
+                                "description": "Do not expand beyond test code or code outside the project"
+                            },
+                            "stop_glob": {
+                                "type": ["string", "array"],
+                                "items": { "type": "string" },
+                                "description": "Do not expand beyond files matching these globs"
+                            },
+                            "shape": {
+                                "type": "string",
+                                "enum": ["tree", "dag"],
+                                "description": "'tree' nests children under each node; 'dag' lists each node once with an edge list",
+                                "default": "tree"
+                            }
+                        },
+                        "required": ["id"]
+                    }
+                },
+                {
+                    "name": "find_call_paths",
+                    "description": "Find the shortest call chains from one symbol to another, following callee edges. Reports explicitly when the symbols are not connected.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "from": {
+                                "type": ["integer", "string"],
+                                "description": "Starting symbol: ID, name, qualified path, file#name or stable key"
+                            },
+                            "to": {
+                                "type": ["integer", "string"],
+                                "description": "Target symbol: ID, name, qualified path, file#name or stable key"
+                            },
+                            "k": {
+                                "type": "integer",
+                                "description": "Number of shortest paths to return (1-10)",
+                                "default": 1,
+                                "minimum": 1,
+                                "maximum": 10
+                            },
+                            "max_depth": {
+                                "type": "integer",
+                                "description": "Maximum number of hops per path (1-20)",
+                                "default": 10,
+                                "minimum": 1,
+                                "maximum": 20
+                            }
+                        },
+                        "required": ["from", "to"]
+                    }
+                },
+                {
+                    "name": "export_call_graph",
+                    "description": "Render the call graph around a symbol, a file or a directory as Graphviz DOT, a Mermaid flowchart or GraphML, for design docs and PR comments.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "symbol": {
+                                "type": ["integer", "string"],
+                                "description": "Symbol ID, symbol name, qualified path, file#name or stable key"
+                            },
+                            "file": {
+                                "type": "string",
+                                "description": "File path relative to project root; all of its symbols are seeds"
+                            },
+                            "directory": {
+                                "type": "string",
+                                "description": "Directory relative to project root; all symbols in it are seeds"
+                            },
+                            "format": {
+                                "type": "string",
+                                "enum": ["dot", "mermaid", "graphml"],
+                                "default": "mermaid"
+                            },
+                            "direction": {
+                                "type": "string",
+                                "enum": ["both", "callers", "callees"],
+                                "default": "both"
+                            },
+                            "depth": {
+                                "type": "integer",
+                                "description": "Hops to follow from the seeds (1-5)",
+                                "default": 2,
+                                "minimum": 1,
+                                "maximum": 5
+                            },
+                            "cluster": {
+                                "type": "string",
+                                "enum": ["none", "file", "module"],
+                                "description": "Group nodes by file or by containing directory",
+                                "default": "file"
+                            },
+                            "max_edges": {
+                                "type": "integer",
+                                "description": "Maximum number of edges to draw (1-2000)",
+                                "default": 200,
+                                "minimum": 1,
+                                "maximum": 2000
+                            }
+                        }
+                    }
+                },
+                {
+                    "name": "get_dependency_graph",
+                    "description": "Aggregate symbol references into a file, directory or package dependency graph. Edge weights count references; each node reports fan-in and fan-out.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "level": {
+                                "type": "string",
+                                "enum": ["file", "directory", "package"],
+                                "description": "Granularity of nodes; packages are directories containing a manifest such as Cargo.toml or package.json",
+                                "default": "file"
+                            },
+                            "path": {
+                                "type": "string",
+                                "description": "Only include dependencies into or out of this file or directory (e.g., 'storage/')"
+                            },
+                            "depth": {
+                                "type": "integer",
+                                "description": "Directory level only: group by the first N path components",
+                                "minimum": 1
+                            },
+                            "min_weight": {
+                                "type": "integer",
+                                "description": "Drop edges with fewer references than this",
+                                "default": 1,
+                                "minimum": 1
+                            }
+                        }
+                    }
+                },
+                {
+                    "name": "find_dependency_cycles",
+                    "description": "Find dependency cycles (strongly connected components) at symbol, file, directory or package level, largest first, with the edges that close each cycle. With a baseline, fails when cycles appear that the baseline does not contain.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "level": {
+                                "type": "string",
+                                "enum": ["symbol", "file", "directory", "package"],
+                                "default": "file"
+                            },
+                            "depth": {
+                                "type": "integer",
+                                "description": "Directory level only: group by the first N path components",
+                                "minimum": 1
+                            },
+                            "max_cycles": {
+                                "type": "integer",
+                                "description": "Maximum number of cycles to return",
+                                "default": 50,
+                                "minimum": 1
+                            },
+                            "baseline": {
+                                "type": ["string", "object", "array"],
+                                "description": "Previously saved result of this tool, inline or as a JSON file path relative to the project root"
+                            }
+                        }
+                    }
+                },
+                {
+                    "name": "find_dead_code",
+                    "description": "Report symbols with no callers or references, grouped by file with a confidence level. Entry points, tests, public API and trait implementations are excluded.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "include_public": {
+                                "type": "boolean",
+                                "description": "Also report unreferenced public API (at low confidence)",
+                                "default": false
+                            },
+                            "ignore_names": {
+                                "type": ["string", "array"],
+                                "items": { "type": "string" },
+                                "description": "Symbol name globs to ignore (e.g., 'handle_*')"
+                            },
+                            "ignore_paths": {
+                                "type": ["string", "array"],
+                                "items": { "type": "string" },
+                                "description": "File path globs to ignore (e.g., 'src/generated/**')"
+                            },
+                            "min_confidence": {
+                                "type": "string",
+                                "enum": ["low", "medium", "high"],
+                                "default": "low"
+                            }
+                        }
+                    }
+                },
+                filter::with_filter_properties(json!({
+                    "name": "find_duplicates",
+                    "description": "Cluster near-duplicate functions across the project by embedding similarity confirmed with a normalized token comparison. Clusters are ranked by how many lines consolidating them would save.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "min_lines": {
+                                "type": "integer",
+                                "description": "Ignore functions shorter than this many lines",
+                                "default": 6,
+                                "minimum": 1
+                            },
+                            "min_similarity": {
+                                "type": "number",
+                                "description": "Token-sequence similarity (0.0-1.0) a pair needs to count as duplicates",
+                                "default": 0.8,
+                                "minimum": 0,
+                                "maximum": 1
+                            },
+                            "neighbours": {
+                                "type": "integer",
+                                "description": "Embedding neighbours compared per function (1-20)",
+                                "default": 5
+                            },
+                            "max_candidates": {
+                                "type": "integer",
+                                "description": "Largest functions to compare (1-2000); each costs one embedding query",
+                                "default": 500
+                            },
+                            "max_clusters": {
+                                "type": "integer",
+                                "description": "Maximum clusters to return",
+                                "default": 20
+                            }
+                        }
+                    }
+                }), false),
+                {
+                    "name": "find_entry_points",
+                    "description": "Find where execution starts: main functions, CLI command handlers, HTTP route handlers, scheduled jobs, library roots, tests, and public callables with no callers. Results are grouped by kind with the files they live in.",
+                    "inputSchema": {
+                        "type": "object",
+                        "properties": {
+                            "kinds": {
+                                "type": "array",
+                                "items": {
+                                    "type": "string",
+                                    "enum": ["main", "cli_command", "http_route", "scheduled_job", "library_root", "test", "uncalled"]
+                                },
+                                "description": "Kinds to report (default: all)"
+                            },
+                            "path": {
+                                "type": ["string", "array"],
+                                "items": { "type": "string" },
+                                "description": "Only look in files matching these globs"
+                            },
+                            "max_per_kind": {
+                                "type": "integer",
+                                "description": "Maximum entries listed per kind (1-500)",
+                                "default": 50,
+                                "minimum": 1,
+                                "maximum": 500
+                            }
+                        }
+                    }
                 },
                 {
-                    "name": "search_files",
-                    "description": "Full-text search for files by path or name.",
+                    "name": "get_neighbourhood",
+                    "description": "Get the k-hop neighbourhood around one or more symbols as a subgraph: nodes with signature, doc comment and optional source, and typed edges (calls, contains, sibling, mentions_type). Use to answer 'how does X work' from one coherent structure.",
                     "inputSchema": {
                         "type": "object",
                         "properties": {
-                            "query": {
-                                "type": "string",
-                                "description": "File path or name pattern"
+                            "ids": {
+                                "type": ["integer", "string", "array"],
+                                "items": { "type": ["integer", "string"] },
+                                "description": "Seed symbols: IDs, names, qualified paths or stable keys"
                             },
-                            "limit": {
+                            "depth": {
+                                "type": "integer",
+                                "description": "Hops from the seeds (1-3)",
+                                "default": 1,
+                                "minimum": 1,
+                                "maximum": 3
+                            },
+                            "edge_kinds": {
+                                "type": "array",
+                                "items": { "type": "string", "enum": ["calls", "contains", "sibling", "mentions_type"] },
+                                "description": "Edge kinds to follow (default: all)"
+                            },
+                            "max_nodes": {
+                                "type": "integer",
+                                "description": "Maximum nodes in the subgraph (1-500)",
+                                "default": 50
+                            },
+                            "max_siblings": {
                                 "type": "integer",
-                                "default": 10
+                                "description": "Siblings followed per node (0-50)",
+                                "default": 5
+                            },
+                            "include_source": {
+                                "type": "boolean",
+                                "description": "Include each node's source text",
+                                "default": false
                             }
                         },
-                        "required": ["query"]
+                        "required": ["ids"]
                     }
                 },
                 {
-                    "name": "get_symbol",
-                    "description": "Get detailed information about a specific symbol by its ID.",
+                    "name": "get_symbol_usage",
+                    "description": "Get complete usage statistics for a symbol including reference count, callers, and callees.",
                     "inputSchema": {
                         "type": "object",
                         "properties": {
                             "id": {
-                                "type": "integer",
-                                "description": "Symbol ID (from search results)"
+                                "type": ["integer", "string"],
+                                "description": "Symbol ID, symbol name, qualified path (crate::module::Type::method), file#name or stable key"
                             }
                         },
                         "required": ["id"]
                     }
                 },
                 {
-                    "name": "get_file",
-                    "description": "Get file information including all symbols defined in it.",
+                    "name": "get_symbol_usages",
+                    "description": "Batch get_symbol_usage: reference counts, callers and callees of several symbols at once, with per-item errors.",
                     "inputSchema": {
                         "type": "object",
                         "properties": {
-                            "path": {
-                                "type": "string",
-                                "description": "File path relative to project root"
+                            "ids": {
+                                "type": "array",
+                                "items": { "type": ["integer", "string"] },
+                                "description": "Symbol IDs, names, qualified paths or stable keys (at most 100)"
                             }
                         },
-                        "required": ["path"]
+                        "required": ["ids"]
                     }
                 },
                 {
-                    "name": "get_callers",
-                    "description": "Find all symbols that call/reference a given symbol.",
+                    "name": "build_context_pack",
+                    "description": "Collect the code relevant to a task in one call: semantic search hits expanded through their callers and callees, with overlapping source ranges merged, ranked and fitted to a token budget. Each snippet says why it was included.",
                     "inputSchema": {
                         "type": "object",
                         "properties": {
-                            "id": {
+                            "task": {
+                                "type": "string",
+                                "description": "Natural-language description of the task"
+                            },
+                            "max_tokens": {
+                                "type": "integer",
+                                "description": "Approximate size of the bundle in tokens (256-32000)",
+                                "default": 4000,
+                                "minimum": 256,
+                                "maximum": 32000
+                            },
+                            "hits": {
+                                "type": "integer",
+                                "description": "Search hits to start from (1-50)",
+                                "default": 10,
+                                "minimum": 1,
+                                "maximum": 50
+                            },
+                            "expand": {
+                                "type": "integer",
+                                "description": "Number of top hits whose callers and callees are added (0-20)",
+                                "default": 5,
+                                "minimum": 0,
+                                "maximum": 20
+                            },
+                            "neighbours": {
                                 "type": "integer",
-                                "description": "Symbol ID to find callers for"
+                                "description": "Callers and callees added per expanded hit, in each direction (0-10)",
+                                "default": 3,
+                                "minimum": 0,
+                                "maximum": 10
                             }
                         },
-                        "required": ["id"]
+                        "required": ["task"]
                     }
                 },
                 {
-                    "name": "get_callees",
-                    "description": "Find all symbols that a given symbol calls/references.",
+                    "name": "get_repo_map",
+                    "description": "Compact outline of the most important files and signatures, ranked by PageRank over the call graph and cut to fit a token budget. Seeds bias the ranking towards the code being worked on.",
                     "inputSchema": {
                         "type": "object",
                         "properties": {
-                            "id": {
+                            "max_tokens": {
                                 "type": "integer",
-                                "description": "Symbol ID to find callees for"
+                                "description": "Approximate size of the map in tokens (128-16000)",
+                                "default": 1024,
+                                "minimum": 128,
+                                "maximum": 16000
+                            },
+                            "seeds": {
+                                "type": "array",
+                                "items": { "type": ["string", "integer"] },
+                                "description": "Files or symbols (ID, name, qualified path or stable key) to personalize the ranking towards"
                             }
-                        },
-                        "required": ["id"]
+                        }
                     }
                 },
                 {
-                    "name": "get_symbol_usage",
-                    "description": "Get complete usage statistics for a symbol including reference count, callers, and callees.",
+                    "name": "get_tree",
+                    "description": "Get the project structure as a hierarchical tree of files and symbols, optionally limited to a subtree, depth, languages or symbol kinds.",
                     "inputSchema": {
                         "type": "object",
                         "properties": {
-                            "id": {
+                            "path": {
+                                "type": "string",
+                                "description": "Only include files under this directory (e.g., 'src/net')"
+                            },
+                            "depth": {
+                                "type": "integer",
+                                "description": "Directory levels below path to expand; deeper directories are collapsed into counts",
+                                "minimum": 0
+                            },
+                            "kind": {
+                                "type": ["string", "array"],
+                                "items": { "type": "string" },
+                                "description": "Only include these symbol kinds (e.g., ['struct', 'trait'])"
+                            },
+                            "symbols": {
+                                "type": "boolean",
+                                "description": "Include symbols; false lists files only",
+                                "default": true
+                            },
+                            "language": {
+                                "type": ["string", "array"],
+                                "items": { "type": "string" },
+                                "description": "Only include files in these languages"
+                            },
+                            "collapse_above": {
                                 "type": "integer",
-                                "description": "Symbol ID"
+                                "description": "Collapse directories with more files than this into counts",
+                                "minimum": 1
                             }
-                        },
-                        "required": ["id"]
+                        }
                     }
                 },
                 {
-                    "name": "get_tree",
-                    "description": "Get the complete project structure as a hierarchical tree of files and symbols.",
+                    "name": "list_directory",
+                    "description": "List the immediate children of a directory: subdirectories with file, symbol and language counts, and files with their symbol counts. Use to explore the project top-down.",
                     "inputSchema": {
                         "type": "object",
-                        "properties": {}
+                        "properties": {
+                            "path": {
+                                "type": "string",
+                                "description": "Directory relative to the project root (default: the root)"
+                            },
+                            "language": {
+                                "type": ["string", "array"],
+                                "items": { "type": "string" },
+                                "description": "Only count files in these languages"
+                            }
+                        }
                     }
                 },
                 {
@@ -346,7 +1120,11 @@ impl McpServer {
                     }
                 }
             ]
-        }))
+        });
+        budget::add_budget_properties(&mut result["tools"]);
+        render::add_format_properties(&mut result["tools"]);
+
+        Ok(result)
     }
 
     async fn handle_tools_call(&mut self, params: Option<Value>) -> Result<Value, JsonRpcError> {
@@ -367,44 +1145,139 @@ impl McpServer {
 
         let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
 
+        let budget = budget::Budget::from_arguments(&arguments)?.filter(|_| !budget::NATIVE_BUDGET_TOOLS.contains(&name));
+        let result = self.call_tool(name, arguments, true).await?;
+        Ok(match budget {
+            Some(budget) => budget.apply_to_tool_result(result),
+            None => result,
+        })
+    }
+
+    /// Runs a tool; `keys` adds stable keys to symbols in its JSON output.
+    async fn call_tool(&self, name: &str, arguments: Value, keys: bool) -> Result<Value, JsonRpcError> {
         let adi = self.adi.as_ref().ok_or_else(|| JsonRpcError {
             code: -32603,
             message: "ADI not initialized. Call initialize first.".to_string(),
             data: None,
         })?;
 
+        if let Some(batch) = batch::batch_tool(name) {
+            // Keys are added once over the whole batch rather than per item
+            let mut items = Vec::new();
+            for (input, item_arguments) in batch.items(&arguments)? {
+                let result = Box::pin(self.call_tool(batch.tool, item_arguments, false)).await;
+                items.push(batch::item_output(input, result));
+            }
+            let output = resolve::serialize(adi, keys, &batch::collect(items));
+            return Ok(tool_result(&serde_json::to_string_pretty(&output).unwrap_or_default()));
+        }
+
         match name {
             "search" => {
-                let query = arguments.get("query").and_then(|v| v.as_str()).unwrap_or("");
                 let limit = arguments.get("limit").and_then(|v| v.as_u64()).unwrap_or(10) as usize;
                 let limit = limit.clamp(1, 100);
-
-                let results = adi.search(query, limit).await.map_err(to_rpc_error)?;
-                Ok(tool_result(&serde_json::to_string_pretty(&results).unwrap_or_default()))
+                let format = render::OutputFormat::from_arguments(&arguments, self.default_format)?;
+                let (query, mut filter) = filter::SearchFilter::from_query(&arguments)?;
+                filter.load_languages(adi);
+
+                match arguments.get("mode").and_then(|v| v.as_str()).unwrap_or("semantic") {
+                    "semantic" => {
+                        let results = filter::fetch_filtered(limit, |n| adi.search(&query, n), |r| filter.matches_symbol(&r.symbol))
+                            .await
+                            .map_err(to_rpc_error)?;
+                        Ok(tool_result(&render::render(format, results.as_slice(), render::Keys::new(adi), || {
+                            serde_json::to_string_pretty(&resolve::serialize(adi, keys, &results)).unwrap_or_default()
+                        })))
+                    }
+                    "hybrid" => {
+                        let weight = arguments.get("semantic_weight").and_then(|v| v.as_f64()).unwrap_or(0.5);
+                        let results = search::hybrid_search(adi, &query, limit, weight, &filter).await.map_err(to_rpc_error)?;
+                        Ok(tool_result(&render::render(format, results.as_slice(), render::Keys::new(adi), || {
+                            serde_json::to_string_pretty(&resolve::serialize(adi, keys, &results)).unwrap_or_default()
+                        })))
+                    }
+                    mode => Err(JsonRpcError {
+                        code: -32602,
+                        message: format!("Unknown search mode: {}", mode),
+                        data: None,
+                    }),
+                }
             }
             "search_symbols" => {
-                let query = arguments.get("query").and_then(|v| v.as_str()).unwrap_or("");
                 let limit = arguments.get("limit").and_then(|v| v.as_u64()).unwrap_or(10) as usize;
-
-                let results = adi.search_symbols(query, limit).await.map_err(to_rpc_error)?;
-                Ok(tool_result(&serde_json::to_string_pretty(&results).unwrap_or_default()))
+                let limit = limit.clamp(1, 100);
+                let format = render::OutputFormat::from_arguments(&arguments, self.default_format)?;
+                let (query, mut filter) = filter::SearchFilter::from_query(&arguments)?;
+                filter.load_languages(adi);
+
+                let results = filter::fetch_filtered(limit, |n| adi.search_symbols(&query, n), |s| filter.matches_symbol(s))
+                    .await
+                    .map_err(to_rpc_error)?;
+                Ok(tool_result(&render::render(format, results.as_slice(), render::Keys::new(adi), || {
+                    serde_json::to_string_pretty(&resolve::serialize(adi, keys, &results)).unwrap_or_default()
+                })))
             }
             "search_files" => {
-                let query = arguments.get("query").and_then(|v| v.as_str()).unwrap_or("");
                 let limit = arguments.get("limit").and_then(|v| v.as_u64()).unwrap_or(10) as usize;
+                let limit = limit.clamp(1, 100);
+                let (query, filter) = filter::SearchFilter::from_query(&arguments)?;
 
-                let results = adi.search_files(query, limit).await.map_err(to_rpc_error)?;
+                let results = filter::fetch_filtered(limit, |n| adi.search_files(&query, n), |f| filter.matches_file(f))
+                    .await
+                    .map_err(to_rpc_error)?;
                 Ok(tool_result(&serde_json::to_string_pretty(&results).unwrap_or_default()))
             }
-            "get_symbol" => {
-                let id = arguments.get("id").and_then(|v| v.as_i64()).ok_or_else(|| JsonRpcError {
+            "find_similar" => {
+                let id = resolve::symbol_id(adi, &arguments, "id")?;
+                let mut filter = filter::SearchFilter::from_arguments(&arguments)?;
+                filter.load_languages(adi);
+                let options = similar::SimilarOptions {
+                    limit: (arguments.get("limit").and_then(|v| v.as_u64()).unwrap_or(10) as usize).clamp(1, 50),
+                    min_similarity: arguments.get("min_similarity").and_then(|v| v.as_f64()).unwrap_or(0.0) as f32,
+                    filter,
+                };
+
+                let symbol = adi.get_symbol(id).map_err(to_rpc_error)?;
+                let similar = similar::find_similar(adi, &symbol, &options).await.map_err(to_rpc_error)?;
+                let output = json!({
+                    "symbol": navigation::symbol_ref(&symbol),
+                    "similar": similar
+                });
+                Ok(tool_result(&serde_json::to_string_pretty(&resolve::serialize(adi, keys, &output)).unwrap_or_default()))
+            }
+            "grep" => {
+                let pattern = arguments.get("pattern").and_then(|v| v.as_str()).ok_or_else(|| JsonRpcError {
+                    code: -32602,
+                    message: "Missing pattern".to_string(),
+                    data: None,
+                })?;
+                let literal = !arguments.get("regex").and_then(|v| v.as_bool()).unwrap_or(false);
+                let case_sensitive = arguments.get("case_sensitive").and_then(|v| v.as_bool()).unwrap_or(true);
+
+                let regex = grep::build_regex(pattern, literal, case_sensitive).map_err(|e| JsonRpcError {
                     code: -32602,
-                    message: "Missing symbol id".to_string(),
+                    message: format!("Invalid pattern: {}", e),
                     data: None,
                 })?;
 
-                let symbol = adi.get_symbol(adi_core::SymbolId(id)).map_err(to_rpc_error)?;
-                Ok(tool_result(&serde_json::to_string_pretty(&symbol).unwrap_or_default()))
+                let options = grep::GrepOptions {
+                    regex,
+                    filter: filter::SearchFilter::from_arguments(&arguments)?,
+                    context: arguments.get("context").and_then(|v| v.as_u64()).unwrap_or(0).min(50) as usize,
+                    max_results: (arguments.get("max_results").and_then(|v| v.as_u64()).unwrap_or(100) as usize).clamp(1, 1000),
+                };
+
+                let result = grep::grep(adi, &options).map_err(to_rpc_error)?;
+                Ok(tool_result(&serde_json::to_string_pretty(&resolve::serialize(adi, keys, &result)).unwrap_or_default()))
+            }
+            "get_symbol" => {
+                let id = resolve::symbol_id(adi, &arguments, "id")?;
+                let format = render::OutputFormat::from_arguments(&arguments, self.default_format)?;
+
+                let symbol = adi.get_symbol(id).map_err(to_rpc_error)?;
+                Ok(tool_result(&render::render(format, &symbol, render::Keys::new(adi), || {
+                    serde_json::to_string_pretty(&resolve::serialize(adi, keys, &symbol)).unwrap_or_default()
+                })))
             }
             "get_file" => {
                 let path = arguments.get("path").and_then(|v| v.as_str()).ok_or_else(|| JsonRpcError {
@@ -412,43 +1285,454 @@ impl McpServer {
                     message: "Missing file path".to_string(),
                     data: None,
                 })?;
+                let format = render::OutputFormat::from_arguments(&arguments, self.default_format)?;
 
                 let file_info = adi.get_file(std::path::Path::new(path)).map_err(to_rpc_error)?;
-                Ok(tool_result(&serde_json::to_string_pretty(&file_info).unwrap_or_default()))
+                Ok(tool_result(&render::render(format, &file_info, render::Keys::new(adi), || {
+                    serde_json::to_string_pretty(&resolve::serialize(adi, keys, &file_info)).unwrap_or_default()
+                })))
             }
-            "get_callers" => {
-                let id = arguments.get("id").and_then(|v| v.as_i64()).ok_or_else(|| JsonRpcError {
+            "get_file_outline" => {
+                let path = arguments.get("path").and_then(|v| v.as_str()).ok_or_else(|| JsonRpcError {
+                    code: -32602,
+                    message: "Missing file path".to_string(),
+                    data: None,
+                })?;
+                let format = render::OutputFormat::from_arguments(&arguments, self.default_format)?;
+
+                let file_info = adi.get_file(std::path::Path::new(path)).map_err(to_rpc_error)?;
+                let outline = outline::outline(&file_info);
+                Ok(tool_result(&render::render(format, &outline, render::Keys::new(adi), || {
+                    serde_json::to_string_pretty(&resolve::serialize(adi, keys, &outline)).unwrap_or_default()
+                })))
+            }
+            "get_symbol_source" => {
+                let id = resolve::symbol_id(adi, &arguments, "id")?;
+                let context_before = arguments.get("context_before").and_then(|v| v.as_u64()).unwrap_or(0).min(200) as usize;
+                let context_after = arguments.get("context_after").and_then(|v| v.as_u64()).unwrap_or(0).min(200) as usize;
+
+                let symbol = adi.get_symbol(id).map_err(to_rpc_error)?;
+                let file = source::SourceFile::read(adi.project_path(), &symbol.file_path).map_err(to_rpc_error)?;
+                let start = symbol.location.start_line as usize;
+                let end = symbol.location.end_line as usize;
+                let span = file.span(&symbol.file_path, start.saturating_sub(context_before), end.saturating_add(context_after));
+
+                Ok(tool_result(&serde_json::to_string_pretty(&resolve::serialize(adi, keys, &json!({
+                    "symbol": {
+                        "id": symbol.id,
+                        "name": symbol.name,
+                        "kind": symbol.kind.as_str(),
+                        "start_line": start,
+                        "end_line": end
+                    },
+                    "source": span
+                }))).unwrap_or_default()))
+            }
+            "read_lines" => {
+                let path = arguments.get("path").and_then(|v| v.as_str()).ok_or_else(|| JsonRpcError {
+                    code: -32602,
+                    message: "Missing file path".to_string(),
+                    data: None,
+                })?;
+                let start = arguments.get("start_line").and_then(|v| v.as_u64()).unwrap_or(1) as usize;
+                let end = arguments.get("end_line").and_then(|v| v.as_u64()).map_or(usize::MAX, |v| v as usize);
+                if end < start {
+                    return Err(JsonRpcError {
+                        code: -32602,
+                        message: format!("end_line ({}) is before start_line ({})", end, start),
+                        data: None,
+                    });
+                }
+
+                // Only indexed files may be read
+                let path = std::path::Path::new(path);
+                adi.get_file(path).map_err(to_rpc_error)?;
+                let file = source::SourceFile::read(adi.project_path(), path).map_err(to_rpc_error)?;
+                let span = file.span(path, start, end);
+                Ok(tool_result(&serde_json::to_string_pretty(&span).unwrap_or_default()))
+            }
+            "symbol_at_position" | "goto_definition" | "find_references" => {
+                let path = arguments.get("path").and_then(|v| v.as_str()).ok_or_else(|| JsonRpcError {
                     code: -32602,
-                    message: "Missing symbol id".to_string(),
+                    message: "Missing file path".to_string(),
                     data: None,
                 })?;
+                let (line, column) = match (
+                    arguments.get("line").and_then(|v| v.as_u64()),
+                    arguments.get("column").and_then(|v| v.as_u64()),
+                ) {
+                    (Some(line), Some(column)) if line >= 1 && column >= 1 => (line as usize, column as usize),
+                    _ => {
+                        return Err(JsonRpcError {
+                            code: -32602,
+                            message: "Missing or invalid line/column (both are 1-based)".to_string(),
+                            data: None,
+                        })
+                    }
+                };
+                let path = std::path::Path::new(path);
+
+                let result = match name {
+                    "symbol_at_position" => {
+                        let file_info = adi.get_file(path).map_err(to_rpc_error)?;
+                        json!({
+                            "symbol": navigation::symbol_at(&file_info.symbols, line, column).map(navigation::symbol_ref)
+                        })
+                    }
+                    "goto_definition" => {
+                        let (identifier, definitions) = navigation::definitions_at(adi, path, line, column).map_err(to_rpc_error)?;
+                        json!({
+                            "identifier": identifier,
+                            "definitions": definitions.iter().map(navigation::symbol_ref).collect::<Vec<_>>()
+                        })
+                    }
+                    _ => {
+                        let (_, mut targets) = navigation::definitions_at(adi, path, line, column).map_err(to_rpc_error)?;
+                        if targets.is_empty() {
+                            let file_info = adi.get_file(path).map_err(to_rpc_error)?;
+                            targets.extend(navigation::symbol_at(&file_info.symbols, line, column).cloned());
+                        }
+                        let mut results = Vec::new();
+                        for target in &targets {
+                            results.push(json!({
+                                "symbol": navigation::symbol_ref(target),
+                                "references": navigation::references(adi, target).map_err(to_rpc_error)?
+                            }));
+                        }
+                        json!({ "results": results })
+                    }
+                };
+                Ok(tool_result(&serde_json::to_string_pretty(&resolve::serialize(adi, keys, &result)).unwrap_or_default()))
+            }
+            "get_callers" => {
+                let id = resolve::symbol_id(adi, &arguments, "id")?;
+                let format = render::OutputFormat::from_arguments(&arguments, self.default_format)?;
 
-                let callers = adi.get_callers(adi_core::SymbolId(id)).map_err(to_rpc_error)?;
-                Ok(tool_result(&serde_json::to_string_pretty(&callers).unwrap_or_default()))
+                let callers = adi.get_callers(id).map_err(to_rpc_error)?;
+                Ok(tool_result(&render::render(format, callers.as_slice(), render::Keys::new(adi), || {
+                    serde_json::to_string_pretty(&resolve::serialize(adi, keys, &callers)).unwrap_or_default()
+                })))
             }
             "get_callees" => {
-                let id = arguments.get("id").and_then(|v| v.as_i64()).ok_or_else(|| JsonRpcError {
+                let id = resolve::symbol_id(adi, &arguments, "id")?;
+                let format = render::OutputFormat::from_arguments(&arguments, self.default_format)?;
+
+                let callees = adi.get_callees(id).map_err(to_rpc_error)?;
+                Ok(tool_result(&render::render(format, callees.as_slice(), render::Keys::new(adi), || {
+                    serde_json::to_string_pretty(&resolve::serialize(adi, keys, &callees)).unwrap_or_default()
+                })))
+            }
+            "get_call_hierarchy" => {
+                let shape = arguments.get("shape").and_then(|v| v.as_str()).unwrap_or("tree");
+                let dag = match shape {
+                    "tree" => false,
+                    "dag" => true,
+                    _ => {
+                        return Err(JsonRpcError {
+                            code: -32602,
+                            message: format!("Invalid shape: {} (expected 'tree' or 'dag')", shape),
+                            data: None,
+                        })
+                    }
+                };
+                let id = resolve::symbol_id(adi, &arguments, "id")?;
+                let direction = arguments.get("direction").and_then(|v| v.as_str()).unwrap_or("callees");
+                let direction = graph::Direction::parse(direction).ok_or_else(|| JsonRpcError {
                     code: -32602,
-                    message: "Missing symbol id".to_string(),
+                    message: format!("Invalid direction: {} (expected 'callers' or 'callees')", direction),
                     data: None,
                 })?;
+                let stop_at = filter::string_list(arguments.get("stop_at"));
+
+                let options = graph::HierarchyOptions {
+                    direction,
+                    max_depth: (arguments.get("max_depth").and_then(|v| v.as_u64()).unwrap_or(3) as usize).clamp(1, 10),
+                    max_nodes: (arguments.get("max_nodes").and_then(|v| v.as_u64()).unwrap_or(100) as usize).clamp(1, 1000),
+                    boundaries: graph::Boundaries {
+                        tests: stop_at.iter().any(|s| s == "tests"),
+                        external: stop_at.iter().any(|s| s == "external"),
+                        glob: filter::glob_argument(&arguments, "stop_glob")?,
+                    },
+                };
+
+                let mut call_graph = graph::CallGraph::new(adi);
+                let hierarchy = graph::call_hierarchy(&mut call_graph, id, &options);
+                let output = if dag {
+                    resolve::serialize(adi, keys, &hierarchy.into_dag())
+                } else {
+                    resolve::serialize(adi, keys, &hierarchy)
+                };
+                Ok(tool_result(&serde_json::to_string_pretty(&output).unwrap_or_default()))
+            }
+            "find_call_paths" => {
+                let from = resolve::symbol_id(adi, &arguments, "from")?;
+                let to = resolve::symbol_id(adi, &arguments, "to")?;
+                let options = graph::CallPathOptions {
+                    k: (arguments.get("k").and_then(|v| v.as_u64()).unwrap_or(1) as usize).clamp(1, 10),
+                    max_depth: (arguments.get("max_depth").and_then(|v| v.as_u64()).unwrap_or(10) as usize).clamp(1, 20),
+                    max_expansions: 10_000,
+                };
+
+                let mut call_graph = graph::CallGraph::new(adi);
+                let paths = graph::call_paths(&mut call_graph, from, to, &options);
+                // Callers often ask with the endpoints swapped; say so rather than just "no"
+                let reverse = if paths.is_empty() && from != to {
+                    graph::call_paths(&mut call_graph, to, from, &graph::CallPathOptions { k: 1, ..options })
+                } else {
+                    vec![]
+                };
+                let mut hop = |id: adi_core::SymbolId| match call_graph.symbol(id) {
+                    Some(symbol) => navigation::symbol_ref(symbol),
+                    None => json!(call_graph.node(id)),
+                };
+                let (source, target) = (hop(from), hop(to));
+                let paths: Vec<Value> = paths
+                    .iter()
+                    .map(|path| json!({ "length": path.len() - 1, "hops": path.iter().map(|id| hop(*id)).collect::<Vec<_>>() }))
+                    .collect();
+
+                let mut output = json!({
+                    "from": source,
+                    "to": target,
+                    "connected": !paths.is_empty(),
+                    "paths": paths
+                });
+                if paths.is_empty() {
+                    output["message"] = json!(format!(
+                        "No call path from {} to {} within {} hops",
+                        output["from"]["name"].as_str().unwrap_or_default(),
+                        output["to"]["name"].as_str().unwrap_or_default(),
+                        options.max_depth
+                    ));
+                    if let Some(path) = reverse.first() {
+                        output["reverse_length"] = json!(path.len() - 1);
+                        output["message"] = json!(format!("{}; the reverse direction is connected in {} hops", output["message"].as_str().unwrap_or_default(), path.len() - 1));
+                    }
+                }
+                Ok(tool_result(&serde_json::to_string_pretty(&resolve::serialize(adi, keys, &output)).unwrap_or_default()))
+            }
+            "export_call_graph" => {
+                let (rendered, _) = export::export(adi, &arguments)?;
+                Ok(tool_result(&rendered))
+            }
+            "get_dependency_graph" => {
+                let options = dependency_options(&arguments)?;
+                let graph = deps::dependency_graph(adi, &options).map_err(to_rpc_error)?;
+                Ok(tool_result(&serde_json::to_string_pretty(&graph).unwrap_or_default()))
+            }
+            "find_dependency_cycles" => {
+                let level = match arguments.get("level").and_then(|v| v.as_str()).unwrap_or("file") {
+                    "symbol" => None,
+                    level => Some(deps::Level::parse(level).ok_or_else(|| JsonRpcError {
+                        code: -32602,
+                        message: format!("Invalid level: {} (expected 'symbol', 'file', 'directory' or 'package')", level),
+                        data: None,
+                    })?),
+                };
+                let depth = arguments.get("depth").and_then(|v| v.as_u64()).map(|d| d.max(1) as usize);
+                let max_cycles = arguments.get("max_cycles").and_then(|v| v.as_u64()).unwrap_or(50).max(1) as usize;
+
+                let baseline = match arguments.get("baseline") {
+                    None | Some(Value::Null) => None,
+                    Some(Value::String(path)) => {
+                        let full_path = cycles::baseline_path(adi.project_path(), path).map_err(|message| JsonRpcError {
+                            code: -32602,
+                            message,
+                            data: None,
+                        })?;
+                        let text = std::fs::read_to_string(full_path).map_err(|e| JsonRpcError {
+                            code: -32602,
+                            message: format!("Cannot read baseline {}: {}", path, e),
+                            data: None,
+                        })?;
+                        Some(serde_json::from_str::<Value>(&text).map_err(|e| JsonRpcError {
+                            code: -32602,
+                            message: format!("Invalid baseline {}: {}", path, e),
+                            data: None,
+                        })?)
+                    }
+                    Some(value) => Some(value.clone()),
+                };
+                let baseline = baseline
+                    .map(|b| {
+                        cycles::baseline_cycles(&b).ok_or_else(|| JsonRpcError {
+                            code: -32602,
+                            message: "Invalid baseline: expected a previous result or a list of cycles".to_string(),
+                            data: None,
+                        })
+                    })
+                    .transpose()?;
+
+                let found = cycles::dependency_cycles(adi, level, depth).map_err(to_rpc_error)?;
+                let total = found.len();
+                let Some(baseline) = baseline else {
+                    let output = json!({
+                        "level": arguments.get("level").and_then(|v| v.as_str()).unwrap_or("file"),
+                        "cycle_count": total,
+                        "cycles": found.into_iter().take(max_cycles).collect::<Vec<_>>()
+                    });
+                    return Ok(tool_result(&serde_json::to_string_pretty(&output).unwrap_or_default()));
+                };
 
-                let callees = adi.get_callees(adi_core::SymbolId(id)).map_err(to_rpc_error)?;
-                Ok(tool_result(&serde_json::to_string_pretty(&callees).unwrap_or_default()))
+                let new_cycles: Vec<&cycles::Cycle> = found.iter().filter(|c| cycles::is_new_cycle(c, &baseline)).collect();
+                let output = json!({
+                    "level": arguments.get("level").and_then(|v| v.as_str()).unwrap_or("file"),
+                    "cycle_count": total,
+                    "baseline_cycle_count": baseline.len(),
+                    "new_cycle_count": new_cycles.len(),
+                    "new_cycles": new_cycles.iter().take(max_cycles).collect::<Vec<_>>(),
+                    "cycles": found.iter().take(max_cycles).collect::<Vec<_>>()
+                });
+                let text = serde_json::to_string_pretty(&output).unwrap_or_default();
+                if new_cycles.is_empty() {
+                    Ok(tool_result(&text))
+                } else {
+                    Ok(tool_error(&text))
+                }
+            }
+            "find_dead_code" => {
+                let min_confidence = arguments.get("min_confidence").and_then(|v| v.as_str()).unwrap_or("low");
+                let options = deadcode::DeadCodeOptions {
+                    include_public: arguments.get("include_public").and_then(|v| v.as_bool()).unwrap_or(false),
+                    ignore_names: filter::glob_argument(&arguments, "ignore_names")?,
+                    ignore_paths: filter::glob_argument(&arguments, "ignore_paths")?,
+                    min_confidence: deadcode::Confidence::parse(min_confidence).ok_or_else(|| JsonRpcError {
+                        code: -32602,
+                        message: format!("Invalid min_confidence: {} (expected 'low', 'medium' or 'high')", min_confidence),
+                        data: None,
+                    })?,
+                };
+
+                let report = deadcode::dead_code(adi, &options).map_err(to_rpc_error)?;
+                Ok(tool_result(&serde_json::to_string_pretty(&resolve::serialize(adi, keys, &report)).unwrap_or_default()))
+            }
+            "find_duplicates" => {
+                let options = duplicates::DuplicateOptions {
+                    min_lines: arguments.get("min_lines").and_then(|v| v.as_u64()).unwrap_or(6).max(1) as usize,
+                    min_similarity: arguments.get("min_similarity").and_then(|v| v.as_f64()).unwrap_or(0.8).clamp(0.0, 1.0),
+                    neighbours: (arguments.get("neighbours").and_then(|v| v.as_u64()).unwrap_or(5) as usize).clamp(1, 20),
+                    max_candidates: (arguments.get("max_candidates").and_then(|v| v.as_u64()).unwrap_or(500) as usize).clamp(1, 2000),
+                    max_clusters: (arguments.get("max_clusters").and_then(|v| v.as_u64()).unwrap_or(20) as usize).max(1),
+                    filter: filter::SearchFilter::from_arguments(&arguments)?,
+                };
+
+                let report = duplicates::find_duplicates(adi, &options).await.map_err(to_rpc_error)?;
+                Ok(tool_result(&serde_json::to_string_pretty(&resolve::serialize(adi, keys, &report)).unwrap_or_default()))
+            }
+            "find_entry_points" => {
+                let mut kinds = std::collections::BTreeSet::new();
+                for kind in filter::string_list(arguments.get("kinds")) {
+                    kinds.insert(entrypoints::EntryKind::parse(&kind).ok_or_else(|| JsonRpcError {
+                        code: -32602,
+                        message: format!("Invalid entry point kind: {}", kind),
+                        data: None,
+                    })?);
+                }
+                let options = entrypoints::EntryPointOptions {
+                    kinds,
+                    path_glob: filter::glob_argument(&arguments, "path")?,
+                    max_per_kind: (arguments.get("max_per_kind").and_then(|v| v.as_u64()).unwrap_or(50) as usize).clamp(1, 500),
+                };
+
+                let groups = entrypoints::entry_points(adi, &options).map_err(to_rpc_error)?;
+                Ok(tool_result(&serde_json::to_string_pretty(&resolve::serialize(adi, keys, &groups)).unwrap_or_default()))
+            }
+            "get_neighbourhood" => {
+                let mut seeds = Vec::new();
+                let ids = match arguments.get("ids") {
+                    Some(Value::Array(ids)) => ids.clone(),
+                    Some(id) => vec![id.clone()],
+                    None => vec![],
+                };
+                for seed in ids {
+                    let id = resolve::symbol_id(adi, &json!({ "seed": seed }), "seed")?;
+                    seeds.push(adi.get_symbol(id).map_err(to_rpc_error)?);
+                }
+                if seeds.is_empty() {
+                    return Err(JsonRpcError {
+                        code: -32602,
+                        message: "Missing ids".to_string(),
+                        data: None,
+                    });
+                }
+
+                let mut edge_kinds = std::collections::BTreeSet::new();
+                for kind in filter::string_list(arguments.get("edge_kinds")) {
+                    edge_kinds.insert(neighbourhood::EdgeKind::parse(&kind).ok_or_else(|| JsonRpcError {
+                        code: -32602,
+                        message: format!("Invalid edge kind: {} (expected 'calls', 'contains', 'sibling' or 'mentions_type')", kind),
+                        data: None,
+                    })?);
+                }
+                if edge_kinds.is_empty() {
+                    edge_kinds.extend(neighbourhood::EdgeKind::ALL);
+                }
+                let options = neighbourhood::NeighbourhoodOptions {
+                    depth: (arguments.get("depth").and_then(|v| v.as_u64()).unwrap_or(1) as usize).clamp(1, 3),
+                    edge_kinds,
+                    max_nodes: (arguments.get("max_nodes").and_then(|v| v.as_u64()).unwrap_or(50) as usize).clamp(1, 500),
+                    max_siblings: (arguments.get("max_siblings").and_then(|v| v.as_u64()).unwrap_or(5) as usize).min(50),
+                    include_source: arguments.get("include_source").and_then(|v| v.as_bool()).unwrap_or(false),
+                };
+
+                let subgraph = neighbourhood::neighbourhood(adi, &seeds, &options);
+                Ok(tool_result(&serde_json::to_string_pretty(&resolve::serialize(adi, keys, &subgraph)).unwrap_or_default()))
             }
             "get_symbol_usage" => {
-                let id = arguments.get("id").and_then(|v| v.as_i64()).ok_or_else(|| JsonRpcError {
+                let id = resolve::symbol_id(adi, &arguments, "id")?;
+                let format = render::OutputFormat::from_arguments(&arguments, self.default_format)?;
+
+                let usage = adi.get_symbol_usage(id).map_err(to_rpc_error)?;
+                Ok(tool_result(&render::render(format, &usage, render::Keys::new(adi), || {
+                    serde_json::to_string_pretty(&resolve::serialize(adi, keys, &usage)).unwrap_or_default()
+                })))
+            }
+            "build_context_pack" => {
+                let task = arguments.get("task").and_then(|v| v.as_str()).ok_or_else(|| JsonRpcError {
                     code: -32602,
-                    message: "Missing symbol id".to_string(),
+                    message: "Missing task".to_string(),
                     data: None,
                 })?;
+                let options = contextpack::ContextPackOptions {
+                    max_tokens: (arguments.get("max_tokens").and_then(|v| v.as_u64()).unwrap_or(4000) as usize).clamp(256, 32000),
+                    hits: (arguments.get("hits").and_then(|v| v.as_u64()).unwrap_or(10) as usize).clamp(1, 50),
+                    expand: (arguments.get("expand").and_then(|v| v.as_u64()).unwrap_or(5) as usize).min(20),
+                    neighbours: (arguments.get("neighbours").and_then(|v| v.as_u64()).unwrap_or(3) as usize).min(10),
+                };
+
+                let pack = contextpack::context_pack(adi, task, &options).await.map_err(to_rpc_error)?;
+                Ok(tool_result(&serde_json::to_string_pretty(&pack).unwrap_or_default()))
+            }
+            "get_repo_map" => {
+                let max_tokens = (arguments.get("max_tokens").and_then(|v| v.as_u64()).unwrap_or(1024) as usize).clamp(128, 16000);
+                let mut seeds = std::collections::HashSet::new();
+                for seed in arguments.get("seeds").and_then(|v| v.as_array()).into_iter().flatten() {
+                    let file = seed.as_str().and_then(|s| adi.get_file(std::path::Path::new(s)).ok());
+                    match file {
+                        Some(info) => seeds.extend(info.symbols.iter().map(|s| s.id)),
+                        None => {
+                            seeds.insert(resolve::symbol_id(adi, &json!({ "seed": seed }), "seed")?);
+                        }
+                    }
+                }
 
-                let usage = adi.get_symbol_usage(adi_core::SymbolId(id)).map_err(to_rpc_error)?;
-                Ok(tool_result(&serde_json::to_string_pretty(&usage).unwrap_or_default()))
+                let map = repomap::repo_map(adi, &seeds, max_tokens).map_err(to_rpc_error)?;
+                Ok(tool_result(&format!(
+                    "# Repo map: {} files, {} symbols, ~{} tokens\n\n{}",
+                    map.files, map.symbols, map.tokens, map.text
+                )))
             }
             "get_tree" => {
-                let tree = adi.get_tree().map_err(to_rpc_error)?;
-                Ok(tool_result(&serde_json::to_string_pretty(&tree).unwrap_or_default()))
+                let format = render::OutputFormat::from_arguments(&arguments, self.default_format)?;
+                let options = tree::TreeOptions::from_arguments(&arguments)?;
+                let tree = tree::filter_tree(adi.get_tree().map_err(to_rpc_error)?, &options);
+                Ok(tool_result(&render::render(format, &tree, render::Keys::new(adi), || serde_json::to_string_pretty(&tree).unwrap_or_default())))
+            }
+            "list_directory" => {
+                let format = render::OutputFormat::from_arguments(&arguments, self.default_format)?;
+                let options = tree::TreeOptions::from_arguments(&arguments)?;
+                let listing = tree::list_directory(&adi.get_tree().map_err(to_rpc_error)?, &options);
+                Ok(tool_result(&render::render(format, &listing, render::Keys::new(adi), || serde_json::to_string_pretty(&listing).unwrap_or_default())))
             }
             "index" => {
                 let progress = adi.index().await.map_err(to_rpc_error)?;
@@ -460,8 +1744,9 @@ impl McpServer {
                 )))
             }
             "status" => {
+                let format = render::OutputFormat::from_arguments(&arguments, self.default_format)?;
                 let status = adi.status().map_err(to_rpc_error)?;
-                Ok(tool_result(&serde_json::to_string_pretty(&status).unwrap_or_default()))
+                Ok(tool_result(&render::render(format, &status, render::Keys::new(adi), || serde_json::to_string_pretty(&status).unwrap_or_default())))
             }
             _ => Err(JsonRpcError {
                 code: -32602,
@@ -548,6 +1833,7 @@ impl McpServer {
             message: "ADI not initialized".to_string(),
             data: None,
         })?;
+        let budget = budget::Budget::from_arguments(&params)?;
 
         let content = match uri {
             "adi://status" => {
@@ -559,8 +1845,16 @@ impl McpServer {
                     blob: None,
                 }
             }
-            "adi://tree" => {
-                let tree = adi.get_tree().map_err(to_rpc_error)?;
+            _ if uri == "adi://tree" || uri.starts_with("adi://tree/") || uri.starts_with("adi://tree?") => {
+                let rest = uri.strip_prefix("adi://tree").unwrap().trim_start_matches('/');
+                let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+                let mut arguments = query_arguments(query);
+                let path = percent_decode(path);
+                if !path.is_empty() {
+                    arguments.insert("path".to_string(), Value::from(path));
+                }
+                let options = tree::TreeOptions::from_arguments(&Value::Object(arguments))?;
+                let tree = tree::filter_tree(adi.get_tree().map_err(to_rpc_error)?, &options);
                 McpResourceContent {
                     uri: uri.to_string(),
                     mime_type: Some("application/json".to_string()),
@@ -603,15 +1897,21 @@ impl McpServer {
                 }
             }
             _ if uri.starts_with("adi://symbol/") => {
-                let id_str = uri.strip_prefix("adi://symbol/").unwrap();
-                let id: i64 = id_str.parse().map_err(|_| JsonRpcError {
-                    code: -32602,
-                    message: "Invalid symbol ID".to_string(),
-                    data: None,
-                })?;
+                let id_str = percent_decode(uri.strip_prefix("adi://symbol/").unwrap());
+                if id_str.is_empty() {
+                    return Err(JsonRpcError {
+                        code: -32602,
+                        message: "Invalid symbol ID".to_string(),
+                        data: None,
+                    });
+                }
 
-                let symbol = adi.get_symbol(adi_core::SymbolId(id)).map_err(to_rpc_error)?;
-                let usage = adi.get_symbol_usage(adi_core::SymbolId(id)).ok();
+                // Accepts numeric ids as well as stable keys and names
+                let symbol = match id_str.parse::<i64>() {
+                    Ok(id) => adi.get_symbol(adi_core::SymbolId(id)).map_err(to_rpc_error)?,
+                    Err(_) => resolve::resolve(adi, &id_str)?,
+                };
+                let usage = adi.get_symbol_usage(symbol.id).ok();
 
                 let content_obj = json!({
                     "symbol": symbol,
@@ -621,7 +1921,40 @@ impl McpServer {
                 McpResourceContent {
                     uri: uri.to_string(),
                     mime_type: Some("application/json".to_string()),
-                    text: Some(serde_json::to_string_pretty(&content_obj).unwrap_or_default()),
+                    text: Some(serde_json::to_string_pretty(&resolve::with_keys(adi, &content_obj)).unwrap_or_default()),
+                    blob: None,
+                }
+            }
+            _ if uri == "adi://deps" || uri.starts_with("adi://deps/") || uri.starts_with("adi://deps?") => {
+                let rest = uri.strip_prefix("adi://deps").unwrap().trim_start_matches('/');
+                let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+                let mut arguments = query_arguments(query);
+                let path = percent_decode(path);
+                if !path.is_empty() {
+                    arguments.insert("path".to_string(), Value::from(path));
+                }
+                let options = dependency_options(&Value::Object(arguments))?;
+                let graph = deps::dependency_graph(adi, &options).map_err(to_rpc_error)?;
+
+                McpResourceContent {
+                    uri: uri.to_string(),
+                    mime_type: Some("application/json".to_string()),
+                    text: Some(serde_json::to_string_pretty(&graph).unwrap_or_default()),
+                    blob: None,
+                }
+            }
+            _ if uri.starts_with("adi://callgraph/") => {
+                let arguments = export::arguments_from_uri(uri.strip_prefix("adi://callgraph/").unwrap()).ok_or_else(|| JsonRpcError {
+                    code: -32602,
+                    message: format!("Invalid call graph URI: {} (expected adi://callgraph/{{format}}/{{symbol|file|dir}}/{{target}})", uri),
+                    data: None,
+                })?;
+                let (rendered, format) = export::export(adi, &arguments)?;
+
+                McpResourceContent {
+                    uri: uri.to_string(),
+                    mime_type: Some(format.mime_type().to_string()),
+                    text: Some(rendered),
                     blob: None,
                 }
             }
@@ -634,6 +1967,14 @@ impl McpServer {
             }
         };
 
+        let content = match budget {
+            Some(budget) => McpResourceContent {
+                text: content.text.map(|text| budget.apply(&text)),
+                ..content
+            },
+            None => content,
+        };
+
         Ok(json!({
             "contents": [content]
         }))
@@ -689,8 +2030,35 @@ impl McpServer {
                 {
                     "uriTemplate": "adi://symbol/{id}",
                     "name": "Symbol Details",
-                    "description": "Get detailed information about a symbol by ID",
+                    "description": "Get detailed information about a symbol by numeric ID or stable key (e.g., rust:src/lib.rs#Client::connect)",
                     "mimeType": "application/json"
+                },
+                {
+                    "uriTemplate": "adi://tree/{path}",
+                    "name": "Project Subtree",
+                    "description": "Files and symbols under a directory; accepts ?depth=, kind=, symbols=false, language= and collapse_above=",
+                    "mimeType": "application/json"
+                },
+                {
+                    "uriTemplate": "adi://deps/{path}",
+                    "name": "Dependency Graph",
+                    "description": "Dependencies into and out of a file or directory with fan-in/fan-out; accepts ?level=file|directory|package",
+                    "mimeType": "application/json"
+                },
+                {
+                    "uriTemplate": "adi://callgraph/{format}/symbol/{id}",
+                    "name": "Symbol Call Graph",
+                    "description": "Call graph around a symbol as dot, mermaid or graphml; accepts ?depth=, direction=, cluster=, max_edges="
+                },
+                {
+                    "uriTemplate": "adi://callgraph/{format}/file/{path}",
+                    "name": "File Call Graph",
+                    "description": "Call graph around all symbols of a file as dot, mermaid or graphml"
+                },
+                {
+                    "uriTemplate": "adi://callgraph/{format}/dir/{path}",
+                    "name": "Directory Call Graph",
+                    "description": "Call graph around all symbols under a directory as dot, mermaid or graphml"
                 }
             ]
         }))
@@ -841,7 +2209,7 @@ impl McpServer {
             "explain_symbol" => {
                 let symbol_name = arguments.get("symbol_name").and_then(|v| v.as_str()).unwrap_or("");
 
-                let symbols = adi.find_symbols_by_name(symbol_name).unwrap_or_default();
+                let symbols = resolve::resolve_candidates(adi, symbol_name);
                 let usage_info: Vec<_> = symbols.iter().take(3).filter_map(|s| {
                     adi.get_symbol_usage(s.id).ok().map(|u| (s, u))
                 }).collect();
@@ -881,7 +2249,7 @@ impl McpServer {
                     role: "user".to_string(),
                     content: McpContent::Text {
                         text: format!(
-                            "Find code in this codebase that is similar to or implements: {}\n\nUse the 'search' tool with semantic search to find relevant symbols, then analyze them.",
+                            "Find code in this codebase that is similar to or implements: {}\n\nUse the 'search' tool with semantic search to find relevant symbols, then 'find_similar' on the best match to find its nearest neighbours, and analyze them.",
                             description
                         ),
                     },
@@ -891,7 +2259,7 @@ impl McpServer {
                 let target = arguments.get("target").and_then(|v| v.as_str()).unwrap_or("");
                 let direction = arguments.get("direction").and_then(|v| v.as_str()).unwrap_or("both");
 
-                let symbols = adi.find_symbols_by_name(target).unwrap_or_default();
+                let symbols = resolve::resolve_candidates(adi, target);
                 let dep_info: String = symbols.iter().take(1).filter_map(|s| {
                     let callers = if direction != "callees" { adi.get_callers(s.id).ok() } else { None };
                     let callees = if direction != "callers" { adi.get_callees(s.id).ok() } else { None };
@@ -911,10 +2279,11 @@ impl McpServer {
                     role: "user".to_string(),
                     content: McpContent::Text {
                         text: format!(
-                            "Analyze the dependency graph for '{}' (direction: {}).\n\nDependency Information:\n{}",
+                            "Analyze the dependency graph for '{}' (direction: {}).\n\nDependency Information:\n{}{}",
                             target,
                             direction,
-                            if dep_info.is_empty() { "No symbol found".to_string() } else { dep_info }
+                            if dep_info.is_empty() { "No symbol found".to_string() } else { dep_info },
+                            resolve::ambiguity_note(target, &symbols)
                         ),
                     },
                 }]
@@ -952,7 +2321,7 @@ impl McpServer {
             "refactor_suggestions" => {
                 let target = arguments.get("target").and_then(|v| v.as_str()).unwrap_or("");
 
-                let symbols = adi.find_symbols_by_name(target).unwrap_or_default();
+                let symbols = resolve::resolve_candidates(adi, target);
                 let context: String = symbols.iter().take(1).filter_map(|s| {
                     let usage = adi.get_symbol_usage(s.id).ok()?;
                     Some(format!(
@@ -970,9 +2339,10 @@ impl McpServer {
                     role: "user".to_string(),
                     content: McpContent::Text {
                         text: format!(
-                            "Suggest refactoring opportunities for '{}'.\n\nContext:\n{}",
+                            "Suggest refactoring opportunities for '{}'.\n\nContext:\n{}{}",
                             target,
-                            if context.is_empty() { "No symbol found. Try searching with the 'search' tool.".to_string() } else { context }
+                            if context.is_empty() { "No symbol found. Try searching with the 'search' tool.".to_string() } else { context },
+                            resolve::ambiguity_note(target, &symbols)
                         ),
                     },
                 }]
@@ -1106,6 +2476,28 @@ fn to_rpc_error(e: impl std::fmt::Display) -> JsonRpcError {
     }
 }
 
+fn dependency_options(arguments: &Value) -> Result<deps::DependencyOptions, JsonRpcError> {
+    let level = arguments.get("level").and_then(|v| v.as_str()).unwrap_or("file");
+    let level = deps::Level::parse(level).ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: format!("Invalid level: {} (expected 'file', 'directory' or 'package')", level),
+        data: None,
+    })?;
+    let scope = arguments
+        .get("path")
+        .and_then(|v| v.as_str())
+        .map(|p| p.trim_matches('/'))
+        .filter(|p| !p.is_empty() && *p != ".")
+        .map(std::path::PathBuf::from);
+
+    Ok(deps::DependencyOptions {
+        level,
+        scope,
+        depth: arguments.get("depth").and_then(|v| v.as_u64()).map(|d| d.max(1) as usize),
+        min_weight: arguments.get("min_weight").and_then(|v| v.as_u64()).unwrap_or(1).max(1) as usize,
+    })
+}
+
 fn tool_result(text: &str) -> Value {
     json!({
         "content": [{
@@ -1115,6 +2507,50 @@ fn tool_result(text: &str) -> Value {
     })
 }
 
+/// Tool result that reports a failed check rather than a protocol error.
+fn tool_error(text: &str) -> Value {
+    json!({
+        "content": [{
+            "type": "text",
+            "text": text
+        }],
+        "isError": true
+    })
+}
+
+/// Decodes `%XX` escapes in a resource URI segment.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Arguments from a `key=value&...` URI query string; numeric values become numbers.
+fn query_arguments(query: &str) -> serde_json::Map<String, Value> {
+    let mut arguments = serde_json::Map::new();
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode(value);
+        let value = value.parse::<u64>().map(Value::from).unwrap_or(Value::from(value));
+        arguments.insert(percent_decode(key), value);
+    }
+    arguments
+}
+
 fn language_to_mime(lang: &adi_core::Language) -> String {
     match lang {
         adi_core::Language::Rust => "text/x-rust",
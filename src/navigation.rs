@@ -0,0 +1,167 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Position-based navigation: symbol at cursor, definitions and references.
+//!
+//! Lines and columns are 1-based, as shown by editors.
+
+use adi_core::Symbol;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::source::SourceFile;
+
+/// Line and column span of a symbol.
+pub fn location(symbol: &Symbol) -> Value {
+    json!({
+        "path": symbol.file_path,
+        "start_line": symbol.location.start_line,
+        "start_column": symbol.location.start_col,
+        "end_line": symbol.location.end_line,
+        "end_column": symbol.location.end_col
+    })
+}
+
+/// Short reference to a symbol for tool output.
+pub fn symbol_ref(symbol: &Symbol) -> Value {
+    json!({
+        "id": symbol.id,
+        "name": symbol.name,
+        "kind": symbol.kind.as_str(),
+        "location": location(symbol)
+    })
+}
+
+/// Innermost symbol whose span contains the position.
+pub fn symbol_at(symbols: &[Symbol], line: usize, column: usize) -> Option<&Symbol> {
+    symbols
+        .iter()
+        .filter(|s| {
+            let (start_line, end_line) = (s.location.start_line as usize, s.location.end_line as usize);
+            let (start_col, end_col) = (s.location.start_col as usize, s.location.end_col as usize);
+            (start_line < line || (start_line == line && start_col <= column))
+                && (line < end_line || (line == end_line && column <= end_col))
+        })
+        .min_by_key(|s| {
+            (
+                s.location.end_line - s.location.start_line,
+                s.location.end_col.abs_diff(s.location.start_col),
+            )
+        })
+}
+
+/// Identifier under the cursor with its start and end columns.
+pub fn word_at(line: &str, column: usize) -> Option<(String, usize, usize)> {
+    let chars: Vec<char> = line.chars().collect();
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let index = column.checked_sub(1)?;
+    if !chars.get(index).copied().is_some_and(is_ident) {
+        return None;
+    }
+
+    let start = chars[..index].iter().rposition(|c| !is_ident(*c)).map_or(0, |p| p + 1);
+    let end = chars[index..].iter().position(|c| !is_ident(*c)).map_or(chars.len(), |p| index + p);
+    Some((chars[start..end].iter().collect(), start + 1, end))
+}
+
+/// Whole-word occurrences of `word` within `first..=last` lines, as `(line, column)`.
+pub fn find_occurrences(text: &str, word: &str, first: usize, last: usize) -> Vec<(usize, usize)> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let mut occurrences = Vec::new();
+
+    for (i, line) in text.lines().enumerate().skip(first.saturating_sub(1)).take((last + 1).saturating_sub(first.max(1))) {
+        for (byte, _) in line.match_indices(word) {
+            let before = line[..byte].chars().next_back();
+            let after = line[byte + word.len()..].chars().next();
+            if !before.is_some_and(is_ident) && !after.is_some_and(is_ident) {
+                occurrences.push((i + 1, line[..byte].chars().count() + 1));
+            }
+        }
+    }
+
+    occurrences
+}
+
+/// Resolves the identifier at a position to its definitions.
+///
+/// Prefers the symbol defined at the cursor, then callees of the enclosing
+/// symbol, then symbols in the same file, then any indexed symbol by name.
+pub fn definitions_at(adi: &adi_core::Adi, path: &Path, line: usize, column: usize) -> anyhow::Result<(Option<String>, Vec<Symbol>)> {
+    let file_info = adi.get_file(path)?;
+    let file = SourceFile::read(adi.project_path(), path)?;
+    let Some((word, _, _)) = file.text.lines().nth(line.saturating_sub(1)).and_then(|l| word_at(l, column)) else {
+        return Ok((None, vec![]));
+    };
+
+    let enclosing = symbol_at(&file_info.symbols, line, column);
+    if let Some(symbol) = enclosing {
+        if symbol.name == word && symbol.location.start_line as usize == line {
+            return Ok((Some(word), vec![symbol.clone()]));
+        }
+        let callees: Vec<Symbol> = adi
+            .get_callees(symbol.id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|c| c.name == word)
+            .collect();
+        if !callees.is_empty() {
+            return Ok((Some(word), callees));
+        }
+    }
+
+    let local: Vec<Symbol> = file_info.symbols.iter().filter(|s| s.name == word).cloned().collect();
+    if !local.is_empty() {
+        return Ok((Some(word), local));
+    }
+
+    let global = adi.find_symbols_by_name(&word).unwrap_or_default();
+    Ok((Some(word), global))
+}
+
+/// References to `target`: each caller with the exact spans where it names the target.
+pub fn references(adi: &adi_core::Adi, target: &Symbol) -> anyhow::Result<Vec<Value>> {
+    let callers = adi.get_callers(target.id)?;
+    let mut seen = HashSet::new();
+    let mut references = Vec::new();
+
+    for caller in callers {
+        if !seen.insert(caller.id) {
+            continue;
+        }
+        let start = caller.location.start_line as usize;
+        let end = caller.location.end_line as usize;
+        let occurrences = SourceFile::read(adi.project_path(), &caller.file_path)
+            .map(|f| find_occurrences(&f.text, &target.name, start, end))
+            .unwrap_or_default();
+
+        let locations: Vec<Value> = if occurrences.is_empty() {
+            vec![location(&caller)]
+        } else {
+            occurrences
+                .into_iter()
+                .map(|(line, column)| {
+                    json!({
+                        "path": caller.file_path,
+                        "start_line": line,
+                        "start_column": column,
+                        "end_line": line,
+                        "end_column": column + target.name.chars().count() - 1
+                    })
+                })
+                .collect()
+        };
+
+        references.push(json!({
+            "caller": {
+                "id": caller.id,
+                "name": caller.name,
+                "kind": caller.kind.as_str()
+            },
+            "locations": locations
+        }));
+    }
+
+    Ok(references)
+}
@@ -0,0 +1,278 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! k-hop neighbourhoods around seed symbols with typed edges: calls,
+//! containment, siblings in the same file and types named in signatures.
+
+use adi_core::{Symbol, SymbolId, SymbolKind};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::graph::{CallGraph, Direction};
+use crate::source::SourceFile;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    /// `from` calls `to`
+    Calls,
+    /// `from` is the parent (impl, class, module) of `to`
+    Contains,
+    /// `from` and `to` share a parent in the same file
+    Sibling,
+    /// `to` is a type named in the signature of `from`
+    MentionsType,
+}
+
+impl EdgeKind {
+    pub const ALL: [EdgeKind; 4] = [EdgeKind::Calls, EdgeKind::Contains, EdgeKind::Sibling, EdgeKind::MentionsType];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "calls" => Some(EdgeKind::Calls),
+            "contains" => Some(EdgeKind::Contains),
+            "sibling" => Some(EdgeKind::Sibling),
+            "mentions_type" => Some(EdgeKind::MentionsType),
+            _ => None,
+        }
+    }
+}
+
+/// Type names too common to be worth an edge.
+const BUILTIN_TYPES: &[&str] = &[
+    "Self", "String", "Vec", "Option", "Result", "Box", "Rc", "Arc", "HashMap", "HashSet", "BTreeMap", "BTreeSet",
+    "Some", "None", "Ok", "Err", "List", "Dict", "Set", "Tuple", "Any", "Optional", "Union", "Promise", "Array",
+    "Map", "Object", "Error", "T", "U", "K", "V", "E",
+];
+
+const TYPE_KINDS: &[SymbolKind] = &[
+    SymbolKind::Struct,
+    SymbolKind::Class,
+    SymbolKind::Enum,
+    SymbolKind::Interface,
+    SymbolKind::Trait,
+    SymbolKind::Type,
+];
+
+/// Capitalized identifiers in a signature that may name project types, in order of appearance.
+pub fn mentioned_types(signature: &str, own_name: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    signature
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| word.starts_with(|c: char| c.is_uppercase()))
+        .filter(|word| *word != own_name && !BUILTIN_TYPES.contains(word))
+        .filter(|word| seen.insert(word.to_string()))
+        .map(String::from)
+        .collect()
+}
+
+pub struct NeighbourhoodOptions {
+    pub depth: usize,
+    pub edge_kinds: BTreeSet<EdgeKind>,
+    pub max_nodes: usize,
+    /// Siblings taken per node; files with many items would swamp the graph
+    pub max_siblings: usize,
+    pub include_source: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NeighbourhoodNode {
+    pub id: SymbolId,
+    pub name: String,
+    pub kind: String,
+    pub path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Hops from the nearest seed
+    pub hop: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doc_comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NeighbourhoodEdge {
+    pub from: SymbolId,
+    pub to: SymbolId,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Neighbourhood {
+    pub seeds: Vec<SymbolId>,
+    pub nodes: Vec<NeighbourhoodNode>,
+    pub edges: Vec<NeighbourhoodEdge>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+struct Walk<'a> {
+    adi: &'a adi_core::Adi,
+    calls: CallGraph<'a>,
+    files: HashMap<PathBuf, Vec<Symbol>>,
+    types: HashMap<String, Option<Symbol>>,
+}
+
+impl Walk<'_> {
+    fn file_symbols(&mut self, symbol: &Symbol) -> &[Symbol] {
+        let adi = self.adi;
+        self.files
+            .entry(symbol.file_path.clone())
+            .or_insert_with(|| adi.get_file(&symbol.file_path).map(|f| f.symbols).unwrap_or_default())
+    }
+
+    /// The project type a name refers to, when it is unambiguous.
+    fn type_named(&mut self, name: &str) -> Option<Symbol> {
+        let adi = self.adi;
+        self.types
+            .entry(name.to_string())
+            .or_insert_with(|| {
+                let mut types: Vec<Symbol> = adi
+                    .find_symbols_by_name(name)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|s| TYPE_KINDS.contains(&s.kind))
+                    .collect();
+                (types.len() == 1).then(|| types.remove(0))
+            })
+            .clone()
+    }
+
+    /// Typed edges out of `symbol`, as `(edge, neighbour)` with the edge oriented as stored.
+    fn edges(&mut self, symbol: &Symbol, options: &NeighbourhoodOptions) -> Vec<(NeighbourhoodEdge, Symbol)> {
+        let mut found = Vec::new();
+        let wanted = |kind: EdgeKind| options.edge_kinds.contains(&kind);
+        let edge = |from: SymbolId, to: SymbolId, kind: EdgeKind| NeighbourhoodEdge { from, to, kind };
+
+        if wanted(EdgeKind::Calls) {
+            self.calls.insert(symbol.clone());
+            for direction in [Direction::Callees, Direction::Callers] {
+                for id in self.calls.neighbours(symbol.id, direction) {
+                    let Some(neighbour) = self.calls.symbol(id).cloned() else {
+                        continue;
+                    };
+                    let (from, to) = match direction {
+                        Direction::Callees => (symbol.id, id),
+                        Direction::Callers => (id, symbol.id),
+                    };
+                    found.push((edge(from, to, EdgeKind::Calls), neighbour));
+                }
+            }
+        }
+
+        if wanted(EdgeKind::Contains) || wanted(EdgeKind::Sibling) {
+            let file_symbols = self.file_symbols(symbol).to_vec();
+            if wanted(EdgeKind::Contains) {
+                if let Some(parent) = file_symbols.iter().find(|s| Some(s.id) == symbol.parent_id) {
+                    found.push((edge(parent.id, symbol.id, EdgeKind::Contains), parent.clone()));
+                }
+                for child in file_symbols.iter().filter(|s| s.parent_id == Some(symbol.id)) {
+                    found.push((edge(symbol.id, child.id, EdgeKind::Contains), child.clone()));
+                }
+            }
+            if wanted(EdgeKind::Sibling) {
+                let siblings = file_symbols
+                    .iter()
+                    .filter(|s| s.id != symbol.id && s.parent_id == symbol.parent_id)
+                    .take(options.max_siblings);
+                for sibling in siblings {
+                    found.push((edge(symbol.id, sibling.id, EdgeKind::Sibling), sibling.clone()));
+                }
+            }
+        }
+
+        if wanted(EdgeKind::MentionsType) {
+            let names = symbol.signature.as_deref().map(|s| mentioned_types(s, &symbol.name)).unwrap_or_default();
+            for name in names {
+                if let Some(target) = self.type_named(&name).filter(|t| t.id != symbol.id) {
+                    found.push((edge(symbol.id, target.id, EdgeKind::MentionsType), target));
+                }
+            }
+        }
+        found
+    }
+}
+
+fn node(symbol: &Symbol, hop: usize, source: Option<String>) -> NeighbourhoodNode {
+    NeighbourhoodNode {
+        id: symbol.id,
+        name: symbol.name.clone(),
+        kind: symbol.kind.as_str().to_string(),
+        path: symbol.file_path.to_string_lossy().to_string(),
+        start_line: symbol.location.start_line,
+        end_line: symbol.location.end_line,
+        hop,
+        signature: symbol.signature.clone(),
+        doc_comment: symbol.doc_comment.clone(),
+        source,
+    }
+}
+
+/// Breadth-first neighbourhood of `seeds` up to `depth` hops over the wanted edge kinds.
+///
+/// Stops adding nodes at `max_nodes`; edges between nodes already included
+/// are still recorded.
+pub fn neighbourhood(adi: &adi_core::Adi, seeds: &[Symbol], options: &NeighbourhoodOptions) -> Neighbourhood {
+    let mut walk = Walk {
+        adi,
+        calls: CallGraph::new(adi),
+        files: HashMap::new(),
+        types: HashMap::new(),
+    };
+    let mut result = Neighbourhood {
+        seeds: seeds.iter().map(|s| s.id).collect(),
+        nodes: Vec::new(),
+        edges: Vec::new(),
+        truncated: false,
+    };
+
+    let mut included: Vec<(Symbol, usize)> = Vec::new();
+    let mut seen: HashSet<SymbolId> = HashSet::new();
+    let mut seen_edges: HashSet<(SymbolId, SymbolId, EdgeKind)> = HashSet::new();
+    let mut frontier: Vec<Symbol> = seeds.iter().filter(|s| seen.insert(s.id)).cloned().collect();
+    included.extend(frontier.iter().map(|s| (s.clone(), 0)));
+
+    for hop in 1..=options.depth {
+        let mut next = Vec::new();
+        for symbol in &frontier {
+            for (edge, neighbour) in walk.edges(symbol, options) {
+                // Sibling edges are symmetric; keep one orientation
+                let key = match edge.kind {
+                    EdgeKind::Sibling => (edge.from.min(edge.to), edge.from.max(edge.to), edge.kind),
+                    _ => (edge.from, edge.to, edge.kind),
+                };
+                if !seen.contains(&neighbour.id) {
+                    if included.len() >= options.max_nodes {
+                        result.truncated = true;
+                        continue;
+                    }
+                    seen.insert(neighbour.id);
+                    included.push((neighbour.clone(), hop));
+                    next.push(neighbour);
+                }
+                if seen_edges.insert(key) {
+                    result.edges.push(edge);
+                }
+            }
+        }
+        frontier = next;
+    }
+
+    let mut sources: HashMap<PathBuf, Option<SourceFile>> = HashMap::new();
+    for (symbol, hop) in &included {
+        let source = options.include_source.then(|| {
+            let file = sources
+                .entry(symbol.file_path.clone())
+                .or_insert_with(|| SourceFile::read(adi.project_path(), &symbol.file_path).ok());
+            file.as_ref()
+                .map(|f| f.lines(symbol.location.start_line as usize, symbol.location.end_line as usize))
+        });
+        result.nodes.push(node(symbol, *hop, source.flatten()));
+    }
+    result
+}
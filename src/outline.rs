@@ -0,0 +1,113 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Hierarchical outline of a file: items nested under their impl, class or module.
+
+use adi_core::{FileInfo, Symbol, SymbolId};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::render::{signature_line, summary};
+
+#[derive(Debug, Serialize)]
+pub struct OutlineEntry {
+    pub id: SymbolId,
+    pub name: String,
+    pub kind: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// First line of the doc comment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<OutlineEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Outline {
+    pub path: String,
+    pub language: String,
+    pub symbols: usize,
+    pub outline: Vec<OutlineEntry>,
+}
+
+/// Parent of each symbol within the file.
+///
+/// Uses the indexed parent when it is in the file, and otherwise the
+/// innermost symbol whose line range encloses it, for parsers that do not
+/// record parents.
+pub fn parents(symbols: &[Symbol]) -> HashMap<SymbolId, SymbolId> {
+    let ids: HashSet<SymbolId> = symbols.iter().map(|s| s.id).collect();
+    let mut ordered: Vec<&Symbol> = symbols.iter().collect();
+    ordered.sort_by(|a, b| {
+        a.location
+            .start_line
+            .cmp(&b.location.start_line)
+            .then(b.location.end_line.cmp(&a.location.end_line))
+            .then(a.id.cmp(&b.id))
+    });
+
+    let mut parents = HashMap::new();
+    let mut open: Vec<&Symbol> = Vec::new();
+    for symbol in ordered {
+        while open.last().is_some_and(|top| top.location.end_line < symbol.location.start_line) {
+            open.pop();
+        }
+        let parent = match symbol.parent_id.filter(|p| ids.contains(p)) {
+            Some(parent) => Some(parent),
+            None if symbol.parent_id.is_none() => open
+                .iter()
+                .rev()
+                .find(|o| o.location.end_line >= symbol.location.end_line)
+                .map(|o| o.id),
+            None => None,
+        };
+        if let Some(parent) = parent {
+            parents.insert(symbol.id, parent);
+        }
+        open.push(symbol);
+    }
+    parents
+}
+
+fn entry(symbol: &Symbol, children: &HashMap<SymbolId, Vec<&Symbol>>) -> OutlineEntry {
+    OutlineEntry {
+        id: symbol.id,
+        name: symbol.name.clone(),
+        kind: symbol.kind.as_str().to_string(),
+        start_line: symbol.location.start_line,
+        end_line: symbol.location.end_line,
+        signature: signature_line(symbol),
+        summary: summary(symbol).map(String::from),
+        children: children
+            .get(&symbol.id)
+            .map(|c| c.iter().map(|child| entry(child, children)).collect())
+            .unwrap_or_default(),
+    }
+}
+
+/// Nested outline of a file's symbols, each level ordered by line.
+pub fn outline(info: &FileInfo) -> Outline {
+    let parents = parents(&info.symbols);
+    let mut ordered: Vec<&Symbol> = info.symbols.iter().collect();
+    ordered.sort_by_key(|s| (s.location.start_line, std::cmp::Reverse(s.location.end_line)));
+
+    let mut children: HashMap<SymbolId, Vec<&Symbol>> = HashMap::new();
+    let mut roots = Vec::new();
+    for symbol in ordered {
+        match parents.get(&symbol.id) {
+            Some(parent) => children.entry(*parent).or_default().push(symbol),
+            None => roots.push(symbol),
+        }
+    }
+
+    Outline {
+        path: info.file.path.to_string_lossy().to_string(),
+        language: info.file.language.as_str().to_string(),
+        symbols: info.symbols.len(),
+        outline: roots.into_iter().map(|s| entry(s, &children)).collect(),
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Compact query syntax for the search tools.
+//!
+//! `kind:function lang:rust path:src/net/** name:conn* -path:tests "retry backoff"`
+//!
+//! `key:value` terms with a known key become filters (a leading `-` negates
+//! them); everything else, including quoted phrases and `word:` tokens with
+//! other keys, is free text passed on to the search.
+
+use std::fmt;
+
+/// Filter keys accepted in queries, with their aliases.
+const KEYS: &[(&str, &[&str])] = &[
+    ("kind", &["kind"]),
+    ("lang", &["lang", "language"]),
+    ("path", &["path"]),
+    ("name", &["name"]),
+    ("vis", &["vis", "visibility"]),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryTerm {
+    /// Canonical key from [`KEYS`]
+    pub key: &'static str,
+    pub value: String,
+    pub negated: bool,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ParsedQuery {
+    pub text: String,
+    pub terms: Vec<QueryTerm>,
+}
+
+impl ParsedQuery {
+    /// Text to send to the search backend.
+    ///
+    /// Falls back to the literal part of a `name:` pattern when the query has
+    /// no free text, so `name:conn*` alone still searches for `conn`.
+    pub fn search_text(&self) -> String {
+        if !self.text.is_empty() {
+            return self.text.clone();
+        }
+        self.terms
+            .iter()
+            .filter(|t| t.key == "name" && !t.negated)
+            .map(|t| t.value.split(['*', '?', '[', '{']).find(|p| !p.is_empty()).unwrap_or(""))
+            .find(|p| !p.is_empty())
+            .unwrap_or("")
+            .to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuerySyntaxError {
+    /// 0-based character offset into the query
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for QuerySyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Query syntax error at position {}: {}", self.position, self.message)
+    }
+}
+
+pub fn parse(input: &str) -> Result<ParsedQuery, QuerySyntaxError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut parsed = ParsedQuery::default();
+    let mut text = Vec::new();
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        if chars[pos].is_whitespace() {
+            pos += 1;
+            continue;
+        }
+
+        let start = pos;
+        if chars[pos] == '"' {
+            let (phrase, end) = read_quoted(&chars, pos)?;
+            text.push(phrase);
+            pos = end;
+            continue;
+        }
+
+        let negated = chars[pos] == '-';
+        let key_start = if negated { pos + 1 } else { pos };
+        let mut key_end = key_start;
+        while key_end < chars.len() && (chars[key_end].is_ascii_alphabetic() || chars[key_end] == '_') {
+            key_end += 1;
+        }
+
+        // `key:value`, but not `std::io`-style paths
+        let is_term = key_end > key_start
+            && chars.get(key_end) == Some(&':')
+            && chars.get(key_end + 1) != Some(&':');
+        if !is_term {
+            let end = token_end(&chars, pos);
+            text.push(chars[start..end].iter().collect());
+            pos = end;
+            continue;
+        }
+
+        let key_text: String = chars[key_start..key_end].iter().collect::<String>().to_lowercase();
+        // Unknown keys are ordinary words: URLs, `error: timeout`, `TODO: fix`
+        let Some(key) = KEYS
+            .iter()
+            .find(|(_, aliases)| aliases.contains(&key_text.as_str()))
+            .map(|(key, _)| *key)
+        else {
+            let end = token_end(&chars, pos);
+            text.push(chars[start..end].iter().collect());
+            pos = end;
+            continue;
+        };
+
+        let value_start = key_end + 1;
+        let (value, end) = if chars.get(value_start) == Some(&'"') {
+            read_quoted(&chars, value_start)?
+        } else {
+            let end = token_end(&chars, value_start);
+            (chars[value_start..end].iter().collect(), end)
+        };
+        if value.is_empty() {
+            return Err(QuerySyntaxError {
+                position: value_start,
+                message: format!("missing value for '{}:'", key_text),
+            });
+        }
+
+        parsed.terms.push(QueryTerm { key, value, negated });
+        pos = end;
+    }
+
+    parsed.text = text.join(" ");
+    Ok(parsed)
+}
+
+/// Reads a `"quoted phrase"` starting at `start`; returns it and the position after the closing quote.
+fn read_quoted(chars: &[char], start: usize) -> Result<(String, usize), QuerySyntaxError> {
+    let close = chars[start + 1..]
+        .iter()
+        .position(|c| *c == '"')
+        .ok_or_else(|| QuerySyntaxError {
+            position: start,
+            message: "unterminated quote".to_string(),
+        })?;
+    let end = start + 1 + close;
+    Ok((chars[start + 1..end].iter().collect(), end + 1))
+}
+
+fn token_end(chars: &[char], start: usize) -> usize {
+    chars[start..]
+        .iter()
+        .position(|c| c.is_whitespace())
+        .map_or(chars.len(), |p| start + p)
+}
@@ -0,0 +1,471 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Markdown and compact text renderings of tool output.
+//!
+//! JSON stays the default; the other formats drop braces, quotes and
+//! repeated keys. Compact output is one line per symbol:
+//! `#id path:line kind signature (key)`.
+
+use adi_core::{FileInfo, SearchResult, Status, Symbol, SymbolNode, SymbolUsage};
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::outline::{Outline, OutlineEntry};
+use crate::resolve::KeyIndex;
+use crate::search::HybridHit;
+use crate::tree::{DirectoryListing, DirectorySummary, FilteredTree};
+use crate::JsonRpcError;
+
+/// Tools with markdown and compact renderers.
+pub const FORMATTED_TOOLS: &[&str] = &[
+    "search",
+    "search_symbols",
+    "get_symbol",
+    "get_file",
+    "get_file_outline",
+    "get_callers",
+    "get_callees",
+    "get_symbol_usage",
+    "get_tree",
+    "list_directory",
+    "status",
+];
+
+/// Environment variable with the default format, optionally per client:
+/// `compact` or `claude-code=compact,cursor=markdown,json`.
+pub const FORMAT_ENV: &str = "ADI_MCP_FORMAT";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Json,
+    Markdown,
+    Compact,
+}
+
+impl OutputFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "json" => Some(OutputFormat::Json),
+            "markdown" | "md" => Some(OutputFormat::Markdown),
+            "compact" | "text" => Some(OutputFormat::Compact),
+            _ => None,
+        }
+    }
+
+    /// Reads the `format` argument, falling back to `default`.
+    pub fn from_arguments(arguments: &Value, default: Self) -> Result<Self, JsonRpcError> {
+        match arguments.get("format").and_then(|v| v.as_str()) {
+            None => Ok(default),
+            Some(format) => Self::parse(format).ok_or_else(|| JsonRpcError {
+                code: -32602,
+                message: format!("Invalid format: {} (expected 'json', 'markdown' or 'compact')", format),
+                data: None,
+            }),
+        }
+    }
+
+    /// Default format for a client from a `client=format` list.
+    ///
+    /// An entry without a client name applies to every client not listed;
+    /// client names match case-insensitively. Entries with an unknown format
+    /// are logged and skipped.
+    pub fn client_default(config: &str, client: Option<&str>) -> Option<Self> {
+        let mut fallback = None;
+        let mut matched = None;
+        for entry in config.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (name, format) = match entry.split_once('=') {
+                Some((name, format)) => (Some(name.trim()), format.trim()),
+                None => (None, entry),
+            };
+            let Some(parsed) = Self::parse(format) else {
+                warn!("Ignoring {} entry '{}': unknown format '{}'", FORMAT_ENV, entry, format);
+                continue;
+            };
+            match name {
+                Some(name) if client.is_some_and(|c| c.eq_ignore_ascii_case(name)) => {
+                    matched.get_or_insert(parsed);
+                }
+                Some(_) => {}
+                None => fallback = Some(parsed),
+            }
+        }
+        matched.or(fallback)
+    }
+}
+
+/// Adds the `format` property to the input schema of every tool with renderers.
+pub fn add_format_properties(tools: &mut Value) {
+    for tool in tools.as_array_mut().into_iter().flatten() {
+        if !tool["name"].as_str().is_some_and(|name| FORMATTED_TOOLS.contains(&name)) {
+            continue;
+        }
+        if let Some(properties) = tool.pointer_mut("/inputSchema/properties").and_then(|p| p.as_object_mut()) {
+            properties.insert(
+                "format".to_string(),
+                json!({
+                    "type": "string",
+                    "enum": ["json", "markdown", "compact"],
+                    "description": "Output format; compact is one line per symbol (default: json, or the client's configured default)"
+                }),
+            );
+        }
+    }
+}
+
+/// Stable keys for rendered symbols; without an index only ids are shown.
+#[derive(Default)]
+pub struct Keys<'a>(Option<KeyIndex<'a>>);
+
+impl<'a> Keys<'a> {
+    pub fn new(adi: &'a adi_core::Adi) -> Self {
+        Self(Some(KeyIndex::new(adi)))
+    }
+
+    fn key(&mut self, symbol: &Symbol) -> Option<String> {
+        self.0.as_mut().map(|index| index.key(symbol).to_string())
+    }
+}
+
+pub trait Render {
+    fn markdown(&self, keys: &mut Keys) -> String;
+    fn compact(&self, keys: &mut Keys) -> String;
+}
+
+/// Renders `value` in `format`; `json` produces the JSON form, which callers
+/// build themselves since some annotate it with stable keys.
+pub fn render<T: Render + ?Sized>(format: OutputFormat, value: &T, mut keys: Keys, json: impl FnOnce() -> String) -> String {
+    match format {
+        OutputFormat::Json => json(),
+        OutputFormat::Markdown => value.markdown(&mut keys),
+        OutputFormat::Compact => value.compact(&mut keys),
+    }
+}
+
+/// First line of a symbol's signature without a trailing opening brace.
+pub fn signature_line(symbol: &Symbol) -> Option<String> {
+    symbol
+        .signature
+        .as_deref()
+        .and_then(|s| s.lines().next())
+        .map(|s| s.trim().trim_end_matches('{').trim_end().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+fn location(symbol: &Symbol) -> String {
+    format!("{}:{}", symbol.file_path.display(), symbol.location.start_line)
+}
+
+/// `#id path:line kind signature (key)`, with the name when there is no signature.
+fn symbol_line(symbol: &Symbol, keys: &mut Keys) -> String {
+    let mut line = format!(
+        "#{} {} {} {}",
+        symbol.id.0,
+        location(symbol),
+        symbol.kind.as_str(),
+        signature_line(symbol).unwrap_or_else(|| symbol.name.clone())
+    );
+    if let Some(key) = keys.key(symbol) {
+        line.push_str(&format!(" ({})", key));
+    }
+    line
+}
+
+/// Markdown list item: name, kind, location, id, key and signature.
+fn symbol_item(symbol: &Symbol, keys: &mut Keys) -> String {
+    let mut item = format!("**{}** *{}* `{}` #{}", symbol.name, symbol.kind.as_str(), location(symbol), symbol.id.0);
+    if let Some(key) = keys.key(symbol) {
+        item.push_str(&format!(" `{}`", key));
+    }
+    if let Some(signature) = signature_line(symbol) {
+        item.push_str(&format!(" — `{}`", signature));
+    }
+    item
+}
+
+/// First non-empty line of a doc comment or description.
+pub fn summary(symbol: &Symbol) -> Option<&str> {
+    symbol
+        .doc_comment
+        .as_deref()
+        .or(symbol.description.as_deref())
+        .and_then(|d| d.lines().map(str::trim).find(|l| !l.is_empty()))
+}
+
+fn symbol_list_markdown(symbols: &[Symbol], keys: &mut Keys) -> String {
+    if symbols.is_empty() {
+        return "_No symbols._\n".to_string();
+    }
+    symbols.iter().map(|s| format!("- {}\n", symbol_item(s, keys))).collect()
+}
+
+fn symbol_list_compact(symbols: &[Symbol], keys: &mut Keys) -> String {
+    symbols.iter().map(|s| format!("{}\n", symbol_line(s, keys))).collect()
+}
+
+fn hits_markdown<'a>(hits: impl Iterator<Item = (&'a Symbol, f64)>, keys: &mut Keys) -> String {
+    let lines: String = hits
+        .enumerate()
+        .map(|(i, (symbol, score))| format!("{}. {} (score {:.3})\n", i + 1, symbol_item(symbol, keys), score))
+        .collect();
+    if lines.is_empty() {
+        "_No results._\n".to_string()
+    } else {
+        lines
+    }
+}
+
+fn hits_compact<'a>(hits: impl Iterator<Item = (&'a Symbol, f64)>, keys: &mut Keys) -> String {
+    hits.map(|(symbol, score)| format!("{:.3} {}\n", score, symbol_line(symbol, keys))).collect()
+}
+
+impl Render for [SearchResult] {
+    fn markdown(&self, keys: &mut Keys) -> String {
+        hits_markdown(self.iter().map(|r| (&r.symbol, r.score as f64)), keys)
+    }
+
+    fn compact(&self, keys: &mut Keys) -> String {
+        hits_compact(self.iter().map(|r| (&r.symbol, r.score as f64)), keys)
+    }
+}
+
+impl Render for [HybridHit] {
+    fn markdown(&self, keys: &mut Keys) -> String {
+        hits_markdown(self.iter().map(|h| (&h.symbol, h.score)), keys)
+    }
+
+    fn compact(&self, keys: &mut Keys) -> String {
+        hits_compact(self.iter().map(|h| (&h.symbol, h.score)), keys)
+    }
+}
+
+impl Render for [Symbol] {
+    fn markdown(&self, keys: &mut Keys) -> String {
+        symbol_list_markdown(self, keys)
+    }
+
+    fn compact(&self, keys: &mut Keys) -> String {
+        symbol_list_compact(self, keys)
+    }
+}
+
+impl Render for Symbol {
+    fn markdown(&self, keys: &mut Keys) -> String {
+        let mut text = format!("### {} `{}`\n\n", self.kind.as_str(), self.name);
+        text.push_str(&format!("- **Id:** {}\n", self.id.0));
+        if let Some(key) = keys.key(self) {
+            text.push_str(&format!("- **Key:** `{}`\n", key));
+        }
+        text.push_str(&format!(
+            "- **Location:** `{}:{}-{}`\n- **Visibility:** {:?}\n",
+            self.file_path.display(),
+            self.location.start_line,
+            self.location.end_line,
+            self.visibility
+        ));
+        if let Some(signature) = self.signature.as_deref() {
+            text.push_str(&format!("\n```\n{}\n```\n", signature.trim()));
+        }
+        if let Some(doc) = self.doc_comment.as_deref().or(self.description.as_deref()) {
+            text.push_str(&format!("\n{}\n", doc.trim()));
+        }
+        text
+    }
+
+    fn compact(&self, keys: &mut Keys) -> String {
+        let mut text = format!("{}\n", symbol_line(self, keys));
+        if let Some(summary) = summary(self) {
+            text.push_str(&format!("  {}\n", summary));
+        }
+        text
+    }
+}
+
+impl Render for FileInfo {
+    fn markdown(&self, keys: &mut Keys) -> String {
+        let mut text = format!("### `{}` ({}, {} symbols)\n\n", self.file.path.display(), self.file.language.as_str(), self.symbols.len());
+        if let Some(description) = self.file.description.as_deref() {
+            text.push_str(&format!("{}\n\n", description.trim()));
+        }
+        text.push_str(&symbol_list_markdown(&self.symbols, keys));
+        text
+    }
+
+    fn compact(&self, keys: &mut Keys) -> String {
+        let mut text = format!("{} ({}, {} symbols)\n", self.file.path.display(), self.file.language.as_str(), self.symbols.len());
+        for symbol in &self.symbols {
+            text.push_str(&format!(
+                "#{} {} {} {}",
+                symbol.id.0,
+                symbol.location.start_line,
+                symbol.kind.as_str(),
+                signature_line(symbol).unwrap_or_else(|| symbol.name.clone())
+            ));
+            if let Some(key) = keys.key(symbol) {
+                text.push_str(&format!(" ({})", key));
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+impl Render for SymbolUsage {
+    fn markdown(&self, keys: &mut Keys) -> String {
+        let mut text = format!("### {}\n\n{} references\n", symbol_item(&self.symbol, keys), self.reference_count);
+        text.push_str(&format!("\n#### Callers ({})\n\n", self.callers.len()));
+        text.push_str(&symbol_list_markdown(&self.callers, keys));
+        text.push_str(&format!("\n#### Callees ({})\n\n", self.callees.len()));
+        text.push_str(&symbol_list_markdown(&self.callees, keys));
+        text
+    }
+
+    fn compact(&self, keys: &mut Keys) -> String {
+        let mut text = format!("{} ({} references)\n", symbol_line(&self.symbol, keys), self.reference_count);
+        for (label, symbols) in [("callers", &self.callers), ("callees", &self.callees)] {
+            text.push_str(&format!("{} ({}):\n", label, symbols.len()));
+            for symbol in symbols {
+                text.push_str(&format!("  {}\n", symbol_line(symbol, keys)));
+            }
+        }
+        text
+    }
+}
+
+fn tree_nodes(nodes: &[SymbolNode], depth: usize, markdown: bool, out: &mut String) {
+    for node in nodes {
+        let indent = "  ".repeat(depth);
+        if markdown {
+            out.push_str(&format!(
+                "{}- {} `{}` (L{}-{})\n",
+                indent,
+                node.kind.as_str(),
+                node.name,
+                node.location.start_line,
+                node.location.end_line
+            ));
+        } else {
+            out.push_str(&format!("{}{} {} {}\n", indent, node.location.start_line, node.kind.as_str(), node.name));
+        }
+        tree_nodes(&node.children, depth + 1, markdown, out);
+    }
+}
+
+fn languages(summary: &DirectorySummary) -> String {
+    summary.languages.iter().map(|(language, files)| format!("{} {}", language, files)).collect::<Vec<_>>().join(", ")
+}
+
+/// `dir/ (N files, M symbols: rust 3, python 1)`
+fn summary_line(summary: &DirectorySummary) -> String {
+    format!("{}/ ({} files, {} symbols: {})", summary.path, summary.files, summary.symbols, languages(summary))
+}
+
+impl Render for FilteredTree {
+    fn markdown(&self, _keys: &mut Keys) -> String {
+        let mut text = String::new();
+        for file in &self.files {
+            text.push_str(&format!("- **{}** ({})\n", file.path.display(), file.language.as_str()));
+            tree_nodes(&file.symbols, 1, true, &mut text);
+        }
+        for summary in &self.collapsed {
+            text.push_str(&format!("- `{}`\n", summary_line(summary)));
+        }
+        text
+    }
+
+    fn compact(&self, _keys: &mut Keys) -> String {
+        let mut text = String::new();
+        for file in &self.files {
+            text.push_str(&format!("{}\n", file.path.display()));
+            tree_nodes(&file.symbols, 1, false, &mut text);
+        }
+        for summary in &self.collapsed {
+            text.push_str(&format!("{}\n", summary_line(summary)));
+        }
+        text
+    }
+}
+
+impl Render for DirectoryListing {
+    fn markdown(&self, _keys: &mut Keys) -> String {
+        let mut text = format!("### `{}/`\n\n", self.path);
+        for directory in &self.directories {
+            text.push_str(&format!("- `{}`\n", summary_line(directory)));
+        }
+        for file in &self.files {
+            text.push_str(&format!("- `{}` ({}, {} symbols)\n", file.path, file.language, file.symbols));
+        }
+        text
+    }
+
+    fn compact(&self, _keys: &mut Keys) -> String {
+        let mut text = String::new();
+        for directory in &self.directories {
+            text.push_str(&format!("{}\n", summary_line(directory)));
+        }
+        for file in &self.files {
+            text.push_str(&format!("{} ({}, {} symbols)\n", file.path, file.language, file.symbols));
+        }
+        text
+    }
+}
+
+fn outline_entries(entries: &[OutlineEntry], depth: usize, markdown: bool, out: &mut String) {
+    for entry in entries {
+        let indent = "  ".repeat(depth);
+        let text = entry.signature.as_deref().unwrap_or(&entry.name);
+        let line = if markdown {
+            format!("{}- {} `{}` (L{}-{}, #{})", indent, entry.kind, text, entry.start_line, entry.end_line, entry.id.0)
+        } else {
+            format!("{}#{} {}-{} {} {}", indent, entry.id.0, entry.start_line, entry.end_line, entry.kind, text)
+        };
+        out.push_str(&line);
+        if let Some(summary) = &entry.summary {
+            out.push_str(&format!(" — {}", summary));
+        }
+        out.push('\n');
+        outline_entries(&entry.children, depth + 1, markdown, out);
+    }
+}
+
+impl Render for Outline {
+    fn markdown(&self, _keys: &mut Keys) -> String {
+        let mut text = format!("### `{}` ({}, {} symbols)\n\n", self.path, self.language, self.symbols);
+        outline_entries(&self.outline, 0, true, &mut text);
+        text
+    }
+
+    fn compact(&self, _keys: &mut Keys) -> String {
+        let mut text = format!("{} ({}, {} symbols)\n", self.path, self.language, self.symbols);
+        outline_entries(&self.outline, 1, false, &mut text);
+        text
+    }
+}
+
+impl Render for Status {
+    fn markdown(&self, _keys: &mut Keys) -> String {
+        format!(
+            "| Field | Value |\n|-------|-------|\n| Files | {} |\n| Symbols | {} |\n| Embedding model | {} ({} dimensions) |\n| Last indexed | {} |\n| Storage | {} bytes |\n",
+            self.indexed_files,
+            self.indexed_symbols,
+            self.embedding_model,
+            self.embedding_dimensions,
+            self.last_indexed.as_deref().unwrap_or("never"),
+            self.storage_size_bytes
+        )
+    }
+
+    fn compact(&self, _keys: &mut Keys) -> String {
+        format!(
+            "files={} symbols={} model={} dimensions={} last_indexed={} storage_bytes={}\n",
+            self.indexed_files,
+            self.indexed_symbols,
+            self.embedding_model,
+            self.embedding_dimensions,
+            self.last_indexed.as_deref().unwrap_or("never"),
+            self.storage_size_bytes
+        )
+    }
+}
@@ -0,0 +1,196 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Repository map: the most central files and signatures within a token budget.
+
+use adi_core::{Symbol, SymbolId};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::deps;
+use crate::graph;
+use crate::render;
+use crate::tokens::estimate_tokens;
+
+const DAMPING: f64 = 0.85;
+const MAX_ITERATIONS: usize = 100;
+const TOLERANCE: f64 = 1e-9;
+
+/// Weighted PageRank; rank flows along edges from caller to callee.
+///
+/// With a non-empty `personalization`, teleports (and rank from nodes without
+/// outgoing edges) go only to those nodes, biasing the ranking towards them.
+pub fn pagerank(nodes: &[SymbolId], edges: &[(SymbolId, SymbolId, usize)], personalization: &HashSet<SymbolId>) -> HashMap<SymbolId, f64> {
+    let n = nodes.len();
+    if n == 0 {
+        return HashMap::new();
+    }
+    let index: HashMap<SymbolId, usize> = nodes.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+
+    let seeds: Vec<usize> = personalization.iter().filter_map(|id| index.get(id).copied()).collect();
+    let teleport: Vec<f64> = if seeds.is_empty() {
+        vec![1.0 / n as f64; n]
+    } else {
+        let mut teleport = vec![0.0; n];
+        seeds.iter().for_each(|&i| teleport[i] = 1.0 / seeds.len() as f64);
+        teleport
+    };
+
+    let mut outgoing: Vec<Vec<(usize, f64)>> = vec![Vec::new(); n];
+    for (from, to, weight) in edges {
+        if let (Some(&from), Some(&to)) = (index.get(from), index.get(to)) {
+            if from != to {
+                outgoing[from].push((to, *weight as f64));
+            }
+        }
+    }
+    let out_weight: Vec<f64> = outgoing.iter().map(|o| o.iter().map(|(_, w)| w).sum()).collect();
+
+    let mut rank = teleport.clone();
+    for _ in 0..MAX_ITERATIONS {
+        let dangling: f64 = (0..n).filter(|&i| out_weight[i] == 0.0).map(|i| rank[i]).sum();
+        let mut next: Vec<f64> = teleport.iter().map(|t| (1.0 - DAMPING + DAMPING * dangling) * t).collect();
+        for (from, targets) in outgoing.iter().enumerate() {
+            for &(to, weight) in targets {
+                next[to] += DAMPING * rank[from] * weight / out_weight[from];
+            }
+        }
+        let delta: f64 = rank.iter().zip(&next).map(|(a, b)| (a - b).abs()).sum();
+        rank = next;
+        if delta < TOLERANCE {
+            break;
+        }
+    }
+
+    nodes.iter().copied().zip(rank).collect()
+}
+
+/// One line of the map: a symbol's signature at its line.
+#[derive(Debug, Clone)]
+pub struct MapEntry {
+    pub id: SymbolId,
+    pub path: PathBuf,
+    pub line: u32,
+    pub text: String,
+    pub rank: f64,
+}
+
+impl MapEntry {
+    pub fn new(symbol: &Symbol, rank: f64) -> Self {
+        let text = render::signature_line(symbol).unwrap_or_else(|| format!("{} {}", symbol.kind.as_str(), symbol.name));
+        Self {
+            id: symbol.id,
+            path: symbol.file_path.clone(),
+            line: symbol.location.start_line,
+            text,
+            rank,
+        }
+    }
+
+    fn render(&self) -> String {
+        format!("{:>5}│ {}\n", self.line, self.text)
+    }
+}
+
+#[derive(Debug)]
+pub struct RepoMap {
+    pub text: String,
+    pub files: usize,
+    pub symbols: usize,
+    pub tokens: usize,
+}
+
+/// Picks the highest ranked entries that fit in `max_tokens` and renders them by file.
+///
+/// Files are ordered by total rank of their shown symbols, symbols by line.
+pub fn render(mut entries: Vec<MapEntry>, max_tokens: usize) -> RepoMap {
+    entries.sort_by(|a, b| b.rank.total_cmp(&a.rank).then(a.id.cmp(&b.id)));
+
+    let mut used = 0;
+    let mut chosen: BTreeMap<PathBuf, Vec<MapEntry>> = BTreeMap::new();
+    for entry in entries {
+        let header = if chosen.contains_key(&entry.path) {
+            0
+        } else {
+            estimate_tokens(&format!("{}:\n", entry.path.display()))
+        };
+        let cost = header + estimate_tokens(&entry.render());
+        if used + cost > max_tokens {
+            continue;
+        }
+        used += cost;
+        chosen.entry(entry.path.clone()).or_default().push(entry);
+    }
+
+    let mut files: Vec<(PathBuf, Vec<MapEntry>)> = chosen.into_iter().collect();
+    files.sort_by(|a, b| {
+        let total = |entries: &[MapEntry]| entries.iter().map(|e| e.rank).sum::<f64>();
+        total(&b.1).total_cmp(&total(&a.1)).then(a.0.cmp(&b.0))
+    });
+
+    let mut text = String::new();
+    let mut symbols = 0;
+    for (path, mut entries) in files.iter().cloned() {
+        entries.sort_by_key(|e| e.line);
+        text.push_str(&format!("{}:\n", path.display()));
+        for entry in &entries {
+            text.push_str(&entry.render());
+        }
+        symbols += entries.len();
+    }
+
+    RepoMap {
+        tokens: estimate_tokens(&text),
+        text,
+        files: files.len(),
+        symbols,
+    }
+}
+
+/// Ranks every indexed symbol and renders the map.
+pub fn repo_map(adi: &adi_core::Adi, seeds: &HashSet<SymbolId>, max_tokens: usize) -> anyhow::Result<RepoMap> {
+    let (_, calls) = deps::collect_calls(adi)?;
+    let mut weights: HashMap<(SymbolId, SymbolId), usize> = HashMap::new();
+    for call in &calls {
+        *weights.entry((call.caller, call.callee)).or_default() += 1;
+    }
+    let edges: Vec<(SymbolId, SymbolId, usize)> = weights.into_iter().map(|((from, to), w)| (from, to, w)).collect();
+
+    let tree = adi.get_tree()?;
+    let mut nodes = Vec::new();
+    for file in &tree.files {
+        graph::collect_ids(&file.symbols, &mut nodes);
+    }
+    let ranks = pagerank(&nodes, &edges, seeds);
+
+    // Only files that can contribute to the budget need their signatures loaded
+    let mut file_ranks: Vec<(&PathBuf, f64)> = tree
+        .files
+        .iter()
+        .map(|f| {
+            let mut ids = Vec::new();
+            graph::collect_ids(&f.symbols, &mut ids);
+            (&f.path, ids.iter().map(|id| ranks.get(id).copied().unwrap_or(0.0)).sum())
+        })
+        .collect();
+    file_ranks.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut entries = Vec::new();
+    let mut budget = 0;
+    for (path, _) in file_ranks {
+        if budget > max_tokens * 2 {
+            break;
+        }
+        let Ok(info) = adi.get_file(path) else {
+            continue;
+        };
+        for symbol in &info.symbols {
+            let entry = MapEntry::new(symbol, ranks.get(&symbol.id).copied().unwrap_or(0.0));
+            budget += estimate_tokens(&entry.render());
+            entries.push(entry);
+        }
+    }
+
+    Ok(render(entries, max_tokens))
+}
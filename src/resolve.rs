@@ -0,0 +1,462 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Resolution of symbol arguments given as ids, names or qualified paths.
+//!
+//! Accepted forms:
+//! - `42` — numeric symbol id
+//! - `connect` — plain symbol name
+//! - `crate::net::Client::connect` or `Client.connect` — qualified path
+//! - `src/net/client.rs#connect` or `src/net/client.rs#Client::connect` — name within a file
+//! - `rust:src/net/client.rs#Client::connect` — stable [`SymbolKey`]
+
+use adi_core::{FileInfo, Symbol, SymbolId};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::JsonRpcError;
+
+/// Maximum number of candidates listed for an ambiguous name.
+const MAX_CANDIDATES: usize = 20;
+
+/// Path segments that carry no module information.
+const IGNORED_SEGMENTS: &[&str] = &["crate", "self", "super", "mod", "lib", "main", "index", "__init__"];
+
+#[derive(Debug, Serialize)]
+pub struct Candidate {
+    pub id: SymbolId,
+    pub name: String,
+    pub qualified_name: String,
+    pub kind: String,
+    pub path: String,
+    pub line: u32,
+    /// Number of qualifier segments matched by the symbol's parents or file path
+    pub score: usize,
+}
+
+pub enum ResolveError {
+    NotFound(String),
+    Ambiguous { query: String, candidates: Vec<Candidate> },
+    /// The key's symbol now lives elsewhere
+    Moved { key: String, current_key: String, id: SymbolId },
+    /// No symbol matches the key any more
+    Deleted(String),
+    Index(String),
+}
+
+impl From<ResolveError> for JsonRpcError {
+    fn from(e: ResolveError) -> Self {
+        match e {
+            ResolveError::NotFound(query) => JsonRpcError {
+                code: -32602,
+                message: format!("No symbol found for '{}'", query),
+                data: None,
+            },
+            ResolveError::Ambiguous { query, candidates } => JsonRpcError {
+                code: -32602,
+                message: format!(
+                    "Ambiguous symbol '{}' matches {} symbols; pass one of these ids:\n{}",
+                    query,
+                    candidates.len(),
+                    candidates
+                        .iter()
+                        .map(|c| format!("  {} {} {} ({}:{})", c.id.0, c.kind, c.qualified_name, c.path, c.line))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                ),
+                data: Some(json!({ "candidates": candidates })),
+            },
+            ResolveError::Moved { key, current_key, id } => JsonRpcError {
+                code: -32602,
+                message: format!("Symbol '{}' moved to '{}' (id {})", key, current_key, id.0),
+                data: Some(json!({ "status": "moved", "key": key, "current_key": current_key, "id": id })),
+            },
+            ResolveError::Deleted(key) => JsonRpcError {
+                code: -32602,
+                message: format!("Symbol '{}' was deleted", key),
+                data: Some(json!({ "status": "deleted", "key": key })),
+            },
+            ResolveError::Index(message) => JsonRpcError {
+                code: -32603,
+                message,
+                data: None,
+            },
+        }
+    }
+}
+
+/// Reads a symbol argument and resolves it to an id.
+///
+/// Numeric ids are passed through without a lookup.
+pub fn symbol_id(adi: &adi_core::Adi, arguments: &Value, key: &str) -> Result<SymbolId, JsonRpcError> {
+    match arguments.get(key) {
+        Some(Value::Number(n)) => n.as_i64().map(SymbolId).ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: format!("Invalid symbol {}", key),
+            data: None,
+        }),
+        Some(Value::String(s)) => match s.trim().parse::<i64>() {
+            Ok(id) => Ok(SymbolId(id)),
+            Err(_) => Ok(resolve(adi, s)?.id),
+        },
+        _ => Err(JsonRpcError {
+            code: -32602,
+            message: format!("Missing symbol {}", key),
+            data: None,
+        }),
+    }
+}
+
+/// Resolves a name, qualified path or `file#name` reference to a single symbol.
+pub fn resolve(adi: &adi_core::Adi, query: &str) -> Result<Symbol, ResolveError> {
+    let query = query.trim();
+    if let Ok(id) = query.parse::<i64>() {
+        return adi
+            .get_symbol(SymbolId(id))
+            .map_err(|_| ResolveError::NotFound(query.to_string()));
+    }
+    if let Some(key) = SymbolKey::parse(query) {
+        return resolve_key(adi, &key);
+    }
+
+    let (file, path) = match query.split_once('#') {
+        Some((file, path)) => (Some(file), path),
+        None => (None, query),
+    };
+    let segments = split_path(path);
+    let Some((name, qualifiers)) = segments.split_last() else {
+        return Err(ResolveError::NotFound(query.to_string()));
+    };
+
+    let symbols = match file {
+        Some(file) => adi
+            .get_file(Path::new(file))
+            .map_err(|e| ResolveError::Index(e.to_string()))?
+            .symbols
+            .into_iter()
+            .filter(|s| s.name == *name)
+            .collect(),
+        None => adi
+            .find_symbols_by_name(name)
+            .map_err(|e| ResolveError::Index(e.to_string()))?,
+    };
+
+    let candidates: Vec<(Symbol, Candidate)> = symbols
+        .into_iter()
+        .map(|s| {
+            let parents = parent_names(adi, &s);
+            let score = qualifier_score(qualifiers, &parents, &s.file_path);
+            let mut qualified = parents.clone();
+            qualified.push(s.name.clone());
+            let candidate = Candidate {
+                id: s.id,
+                name: s.name.clone(),
+                qualified_name: qualified.join("::"),
+                kind: s.kind.as_str().to_string(),
+                path: s.file_path.to_string_lossy().to_string(),
+                line: s.location.start_line,
+                score,
+            };
+            (s, candidate)
+        })
+        .collect();
+    choose(query, qualifiers.len(), candidates)
+}
+
+/// Picks the candidate a query refers to.
+///
+/// A unique best match wins only if it satisfies every qualifier, even when
+/// it is the only candidate; otherwise the ranked candidates are returned.
+pub fn choose(query: &str, qualifiers: usize, mut candidates: Vec<(Symbol, Candidate)>) -> Result<Symbol, ResolveError> {
+    candidates.sort_by(|a, b| {
+        b.1.score
+            .cmp(&a.1.score)
+            .then_with(|| a.1.path.cmp(&b.1.path))
+            .then(a.1.line.cmp(&b.1.line))
+    });
+
+    let best = candidates.first().map_or(0, |c| c.1.score);
+    let tied = candidates.iter().take_while(|c| c.1.score == best).count();
+    match candidates.len() {
+        0 => Err(ResolveError::NotFound(query.to_string())),
+        1 if best == qualifiers => Ok(candidates.remove(0).0),
+        n if n > 1 && tied == 1 && best == qualifiers && best > 0 => Ok(candidates.remove(0).0),
+        _ => Err(ResolveError::Ambiguous {
+            query: query.to_string(),
+            candidates: candidates.into_iter().take(MAX_CANDIDATES).map(|c| c.1).collect(),
+        }),
+    }
+}
+
+/// Splits `a::b::c`, `a.b.c` or `a/b/c` into segments, dropping `crate`/`self`/`super`.
+pub fn split_path(path: &str) -> Vec<&str> {
+    path.split("::")
+        .flat_map(|s| s.split(['.', '/']))
+        .filter(|s| !s.is_empty())
+        .filter(|s| !matches!(*s, "crate" | "self" | "super"))
+        .collect()
+}
+
+/// Counts qualifiers found among the parent symbol names or the file path's module segments.
+pub fn qualifier_score(qualifiers: &[&str], parents: &[String], file_path: &Path) -> usize {
+    let modules: Vec<String> = file_path
+        .with_extension("")
+        .components()
+        .filter_map(|c| c.as_os_str().to_str())
+        .filter(|s| !IGNORED_SEGMENTS.contains(s))
+        .map(String::from)
+        .collect();
+
+    qualifiers
+        .iter()
+        .filter(|q| parents.iter().chain(modules.iter()).any(|p| p == *q))
+        .count()
+}
+
+/// Names of enclosing symbols, outermost first.
+fn parent_names(adi: &adi_core::Adi, symbol: &Symbol) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut parent = symbol.parent_id;
+    while let Some(id) = parent {
+        if names.len() >= 16 {
+            break;
+        }
+        match adi.get_symbol(id) {
+            Ok(p) => {
+                names.push(p.name.clone());
+                parent = p.parent_id;
+            }
+            Err(_) => break,
+        }
+    }
+    names.reverse();
+    names
+}
+
+/// Resolves a prompt argument to its best-ranked symbols.
+///
+/// Returns the single match when unambiguous, otherwise the ranked candidates.
+pub fn resolve_candidates(adi: &adi_core::Adi, query: &str) -> Vec<Symbol> {
+    match resolve(adi, query) {
+        Ok(symbol) => vec![symbol],
+        Err(ResolveError::Ambiguous { candidates, .. }) => candidates
+            .iter()
+            .filter_map(|c| adi.get_symbol(c.id).ok())
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// Prompt note listing the alternatives that were not analyzed.
+pub fn ambiguity_note(query: &str, symbols: &[Symbol]) -> String {
+    if symbols.len() <= 1 {
+        return String::new();
+    }
+    format!(
+        "\n\nNote: '{}' is ambiguous; the best-ranked match was used. Other candidates:\n{}",
+        query,
+        symbols[1..]
+            .iter()
+            .map(|s| format!("  - {} ({}) id={} in {}", s.name, s.kind.as_str(), s.id.0, s.file_path.display()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    )
+}
+
+/// Stable, content-independent symbol identifier that survives re-indexing.
+///
+/// Formatted as `language:path#Qualified::name`, with an `@n` suffix for the
+/// n-th (0-based) symbol sharing the same qualified name in the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolKey {
+    pub language: String,
+    pub path: String,
+    pub qualified_name: String,
+    pub ordinal: usize,
+}
+
+impl SymbolKey {
+    pub fn parse(s: &str) -> Option<Self> {
+        let (head, qualified) = s.split_once('#')?;
+        let (language, path) = head.split_once(':')?;
+        if language.is_empty() || !language.chars().all(|c| c.is_ascii_alphanumeric()) || path.is_empty() {
+            return None;
+        }
+        let (qualified_name, ordinal) = match qualified.rsplit_once('@') {
+            Some((name, n)) => (name, n.parse().ok()?),
+            None => (qualified, 0),
+        };
+        if qualified_name.is_empty() {
+            return None;
+        }
+        Some(Self {
+            language: language.to_string(),
+            path: path.to_string(),
+            qualified_name: qualified_name.to_string(),
+            ordinal,
+        })
+    }
+}
+
+impl fmt::Display for SymbolKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}#{}", self.language, self.path, self.qualified_name)?;
+        if self.ordinal > 0 {
+            write!(f, "@{}", self.ordinal)?;
+        }
+        Ok(())
+    }
+}
+
+/// Computes stable keys, loading only the files of the symbols it is asked
+/// about and caching them with the qualified names.
+pub struct KeyIndex<'a> {
+    adi: &'a adi_core::Adi,
+    files: HashMap<PathBuf, Option<FileInfo>>,
+    qualified: HashMap<SymbolId, String>,
+}
+
+impl<'a> KeyIndex<'a> {
+    pub fn new(adi: &'a adi_core::Adi) -> Self {
+        Self {
+            adi,
+            files: HashMap::new(),
+            qualified: HashMap::new(),
+        }
+    }
+
+    /// The indexed file at `path`, loaded on first use.
+    fn file(&mut self, path: &Path) -> Option<&FileInfo> {
+        let adi = self.adi;
+        self.files
+            .entry(path.to_path_buf())
+            .or_insert_with(|| adi.get_file(path).ok())
+            .as_ref()
+    }
+
+    pub fn qualified_name(&mut self, symbol: &Symbol) -> String {
+        if let Some(name) = self.qualified.get(&symbol.id) {
+            return name.clone();
+        }
+        let mut names = vec![symbol.name.clone()];
+        let mut parent = symbol.parent_id;
+        while let Some(id) = parent {
+            if names.len() > 16 {
+                break;
+            }
+            // Parents are in the same file; only look them up when it is not indexed
+            let found = self
+                .file(&symbol.file_path)
+                .and_then(|info| info.symbols.iter().find(|s| s.id == id))
+                .map(|p| (p.name.clone(), p.parent_id))
+                .or_else(|| self.adi.get_symbol(id).ok().map(|p| (p.name, p.parent_id)));
+            match found {
+                Some((name, next)) => {
+                    names.push(name);
+                    parent = next;
+                }
+                None => break,
+            }
+        }
+        names.reverse();
+        let name = names.join("::");
+        self.qualified.insert(symbol.id, name.clone());
+        name
+    }
+
+    /// Symbols in `path` with the given qualified name, in source order.
+    fn same_name_in_file(&mut self, path: &Path, qualified_name: &str) -> Vec<Symbol> {
+        let short_name = qualified_name.rsplit("::").next().unwrap_or(qualified_name);
+        let mut matches: Vec<Symbol> = self
+            .file(path)
+            .map(|info| info.symbols.iter().filter(|s| s.name == short_name).cloned().collect())
+            .unwrap_or_default();
+        matches.retain(|s| self.qualified_name(s) == qualified_name);
+        matches.sort_by_key(|s| (s.location.start_line, s.location.start_col));
+        matches
+    }
+
+    pub fn key(&mut self, symbol: &Symbol) -> SymbolKey {
+        let qualified_name = self.qualified_name(symbol);
+        let ordinal = self
+            .same_name_in_file(&symbol.file_path, &qualified_name)
+            .iter()
+            .position(|s| s.id == symbol.id)
+            .unwrap_or(0);
+        SymbolKey {
+            language: self
+                .file(&symbol.file_path)
+                .map(|info| info.file.language.as_str().to_string())
+                .unwrap_or_else(|| "unknown".to_string()),
+            path: symbol.file_path.to_string_lossy().to_string(),
+            qualified_name,
+            ordinal,
+        }
+    }
+
+    /// Adds a `key` next to the `id` of every symbol object in `value`.
+    pub fn annotate(&mut self, value: &mut Value) {
+        match value {
+            Value::Array(items) => items.iter_mut().for_each(|v| self.annotate(v)),
+            Value::Object(map) => {
+                let is_symbol = map.get("id").is_some_and(|v| v.is_i64())
+                    && map.get("name").is_some_and(|v| v.is_string())
+                    && (map.contains_key("file_path") || map.contains_key("kind"));
+                if is_symbol && !map.contains_key("key") {
+                    let symbol = serde_json::from_value::<Symbol>(Value::Object(map.clone()))
+                        .ok()
+                        .or_else(|| self.adi.get_symbol(SymbolId(map["id"].as_i64().unwrap_or_default())).ok());
+                    if let Some(symbol) = symbol {
+                        map.insert("key".to_string(), json!(self.key(&symbol).to_string()));
+                    }
+                }
+                map.values_mut().for_each(|v| self.annotate(v));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Serializes tool output with stable keys added to every symbol.
+pub fn with_keys(adi: &adi_core::Adi, output: &impl Serialize) -> Value {
+    let mut value = serde_json::to_value(output).unwrap_or_default();
+    KeyIndex::new(adi).annotate(&mut value);
+    value
+}
+
+/// Serializes tool output, with stable keys only when `keys` is set.
+pub fn serialize(adi: &adi_core::Adi, keys: bool, output: &impl Serialize) -> Value {
+    if keys {
+        with_keys(adi, output)
+    } else {
+        serde_json::to_value(output).unwrap_or_default()
+    }
+}
+
+/// Resolves a stable key, reporting whether its symbol moved or was deleted.
+pub fn resolve_key(adi: &adi_core::Adi, key: &SymbolKey) -> Result<Symbol, ResolveError> {
+    let mut index = KeyIndex::new(adi);
+    let matches = index.same_name_in_file(Path::new(&key.path), &key.qualified_name);
+    if let Some(symbol) = matches.into_iter().nth(key.ordinal) {
+        return Ok(symbol);
+    }
+
+    let short_name = key.qualified_name.rsplit("::").next().unwrap_or(&key.qualified_name);
+    let moved = adi
+        .find_symbols_by_name(short_name)
+        .unwrap_or_default()
+        .into_iter()
+        .find(|s| index.qualified_name(s) == key.qualified_name);
+    match moved {
+        Some(symbol) => Err(ResolveError::Moved {
+            key: key.to_string(),
+            current_key: index.key(&symbol).to_string(),
+            id: symbol.id,
+        }),
+        None => Err(ResolveError::Deleted(key.to_string())),
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Hybrid search combining semantic and full-text rankings.
+
+use adi_core::{Symbol, SymbolId};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::filter::{fetch_filtered, SearchFilter};
+
+/// Rank offset of reciprocal rank fusion; dampens the head of each list.
+pub const RRF_K: f64 = 60.0;
+
+/// Per-result breakdown of a fused score.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScoreComponents {
+    /// 1-based rank in the semantic results
+    pub semantic_rank: Option<usize>,
+    pub semantic_similarity: Option<f32>,
+    /// 1-based rank in the full-text results
+    pub fts_rank: Option<usize>,
+    pub exact_name: bool,
+    pub semantic: f64,
+    pub fts: f64,
+    pub exact: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HybridHit {
+    pub symbol: Symbol,
+    pub score: f64,
+    pub components: ScoreComponents,
+}
+
+/// Fuses two rankings with weighted reciprocal rank fusion.
+///
+/// `semantic_weight` in `0.0..=1.0` sets the share of the semantic list; the
+/// full-text list gets the rest. Symbols named exactly like the query get an
+/// extra rank-1 contribution so they win over loosely related semantic hits.
+pub fn fuse_ranks(
+    semantic: &[SymbolId],
+    fts: &[SymbolId],
+    exact: &[SymbolId],
+    semantic_weight: f64,
+) -> Vec<(SymbolId, f64, ScoreComponents)> {
+    let semantic_weight = semantic_weight.clamp(0.0, 1.0);
+    let fts_weight = 1.0 - semantic_weight;
+    let mut fused: HashMap<SymbolId, ScoreComponents> = HashMap::new();
+
+    for (rank, id) in semantic.iter().enumerate() {
+        let entry = fused.entry(*id).or_default();
+        if entry.semantic_rank.is_none() {
+            entry.semantic_rank = Some(rank + 1);
+            entry.semantic = semantic_weight / (RRF_K + (rank + 1) as f64);
+        }
+    }
+    for (rank, id) in fts.iter().enumerate() {
+        let entry = fused.entry(*id).or_default();
+        if entry.fts_rank.is_none() {
+            entry.fts_rank = Some(rank + 1);
+            entry.fts = fts_weight / (RRF_K + (rank + 1) as f64);
+        }
+    }
+    for id in exact {
+        if let Some(entry) = fused.get_mut(id) {
+            entry.exact_name = true;
+            entry.exact = 1.0 / (RRF_K + 1.0);
+        }
+    }
+
+    let mut ranked: Vec<_> = fused
+        .into_iter()
+        .map(|(id, c)| (id, c.semantic + c.fts + c.exact, c))
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0 .0.cmp(&b.0 .0)));
+    ranked
+}
+
+pub async fn hybrid_search(
+    adi: &adi_core::Adi,
+    query: &str,
+    limit: usize,
+    semantic_weight: f64,
+    filter: &SearchFilter,
+) -> anyhow::Result<Vec<HybridHit>> {
+    // Fetch deeper than the limit so fusion has overlap to work with
+    let fetch = (limit * 2).max(20);
+    let semantic = fetch_filtered(fetch, |n| adi.search(query, n), |r| filter.matches_symbol(&r.symbol)).await?;
+    let fts = fetch_filtered(fetch, |n| adi.search_symbols(query, n), |s| filter.matches_symbol(s)).await?;
+
+    let mut symbols: HashMap<SymbolId, Symbol> = HashMap::new();
+    let mut similarities: HashMap<SymbolId, f32> = HashMap::new();
+    let semantic_ids: Vec<SymbolId> = semantic
+        .into_iter()
+        .map(|r| {
+            let id = r.symbol.id;
+            similarities.entry(id).or_insert(r.score);
+            symbols.entry(id).or_insert(r.symbol);
+            id
+        })
+        .collect();
+    let fts_ids: Vec<SymbolId> = fts
+        .into_iter()
+        .map(|s| {
+            let id = s.id;
+            symbols.entry(id).or_insert(s);
+            id
+        })
+        .collect();
+    let exact_ids: Vec<SymbolId> = symbols
+        .values()
+        .filter(|s| s.name.eq_ignore_ascii_case(query.trim()))
+        .map(|s| s.id)
+        .collect();
+
+    Ok(fuse_ranks(&semantic_ids, &fts_ids, &exact_ids, semantic_weight)
+        .into_iter()
+        .take(limit)
+        .filter_map(|(id, score, mut components)| {
+            components.semantic_similarity = similarities.get(&id).copied();
+            Some(HybridHit {
+                symbol: symbols.remove(&id)?,
+                score,
+                components,
+            })
+        })
+        .collect())
+}
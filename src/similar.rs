@@ -0,0 +1,87 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Nearest neighbours of a symbol in embedding space.
+
+use adi_core::Symbol;
+use serde::Serialize;
+
+use crate::filter::{fetch_filtered, SearchFilter};
+use crate::render::signature_line;
+use crate::source::SourceFile;
+
+/// Longest symbol text embedded as the query; long bodies add little past their head.
+const MAX_QUERY_CHARS: usize = 2000;
+
+/// Text standing in for a symbol's embedding: signature, doc comment and the
+/// start of its body, so the query lands where the symbol itself was embedded.
+pub fn embedding_text(symbol: &Symbol, body: Option<&str>) -> String {
+    let mut text = String::new();
+    for part in [symbol.signature.as_deref(), symbol.doc_comment.as_deref(), body].into_iter().flatten() {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(part.trim());
+    }
+    if text.is_empty() {
+        text = format!("{} {}", symbol.kind.as_str(), symbol.name);
+    }
+    match text.char_indices().nth(MAX_QUERY_CHARS) {
+        Some((end, _)) => text[..end].to_string(),
+        None => text,
+    }
+}
+
+/// Whether `candidate` is `target` itself, also under another id after re-indexing.
+pub fn is_same_symbol(target: &Symbol, candidate: &Symbol) -> bool {
+    candidate.id == target.id
+        || (candidate.file_path == target.file_path
+            && candidate.name == target.name
+            && candidate.location.start_line == target.location.start_line)
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimilarSymbol {
+    pub similarity: f32,
+    pub id: adi_core::SymbolId,
+    pub name: String,
+    pub kind: String,
+    pub path: String,
+    pub line: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+pub struct SimilarOptions {
+    pub limit: usize,
+    pub min_similarity: f32,
+    pub filter: SearchFilter,
+}
+
+/// Symbols closest to `target` by embedding, excluding `target` itself.
+pub async fn find_similar(adi: &adi_core::Adi, target: &Symbol, options: &SimilarOptions) -> anyhow::Result<Vec<SimilarSymbol>> {
+    let source = SourceFile::read(adi.project_path(), &target.file_path).ok();
+    let body = source
+        .as_ref()
+        .map(|s| s.lines(target.location.start_line as usize, target.location.end_line as usize));
+    let query = embedding_text(target, body.as_deref());
+
+    let keep = |r: &adi_core::SearchResult| {
+        !is_same_symbol(target, &r.symbol) && r.score >= options.min_similarity && options.filter.matches_symbol(&r.symbol)
+    };
+    let results = fetch_filtered(options.limit, |n| adi.search(&query, n), keep).await?;
+
+    Ok(results
+        .into_iter()
+        .map(|r| SimilarSymbol {
+            similarity: (r.score * 1000.0).round() / 1000.0,
+            id: r.symbol.id,
+            kind: r.symbol.kind.as_str().to_string(),
+            path: r.symbol.file_path.to_string_lossy().to_string(),
+            line: r.symbol.location.start_line,
+            signature: signature_line(&r.symbol),
+            name: r.symbol.name,
+        })
+        .collect())
+}
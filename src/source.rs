@@ -0,0 +1,86 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Reading source spans and line ranges of indexed files.
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// Full text of a source file together with its content hash.
+pub struct SourceFile {
+    pub text: String,
+    pub hash: String,
+}
+
+impl SourceFile {
+    pub fn read(project_path: &Path, relative_path: &Path) -> std::io::Result<Self> {
+        let bytes = std::fs::read(project_path.join(relative_path))?;
+        let hash = content_hash(&bytes);
+        Ok(Self {
+            text: String::from_utf8_lossy(&bytes).into_owned(),
+            hash,
+        })
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.text.lines().count()
+    }
+
+    /// Raw text of the 1-based inclusive line range `start..=end`, without line numbers.
+    pub fn lines(&self, start: usize, end: usize) -> String {
+        self.text
+            .lines()
+            .skip(start.max(1) - 1)
+            .take(end.saturating_add(1).saturating_sub(start.max(1)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Returns the 1-based inclusive line range `start..=end`, clamped to the file.
+    pub fn span(&self, path: &Path, start: usize, end: usize) -> SourceSpan {
+        let total_lines = self.line_count();
+        let start = start.clamp(1, total_lines.max(1));
+        let end = end.clamp(start, total_lines.max(start));
+
+        let content = self
+            .text
+            .lines()
+            .enumerate()
+            .skip(start - 1)
+            .take(end + 1 - start)
+            .map(|(i, line)| format!("{:>width$} | {}", i + 1, line, width = end.to_string().len()))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        SourceSpan {
+            path: path.to_string_lossy().to_string(),
+            start_line: start,
+            end_line: end,
+            total_lines,
+            content_hash: self.hash.clone(),
+            content,
+        }
+    }
+}
+
+/// A numbered excerpt of a source file.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceSpan {
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub total_lines: usize,
+    /// SHA-256 of the whole file, so callers can detect that it changed.
+    pub content_hash: String,
+    pub content: String,
+}
+
+/// Hex-encoded SHA-256 digest of file contents.
+pub fn content_hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
@@ -2,11 +2,39 @@
 // SPDX-License-Identifier: BSL-1.1
 // See LICENSE file for details
 
+use adi_core::SymbolId;
 use serde_json::{json, Value};
 use std::path::PathBuf;
 use tempfile::TempDir;
 
-use crate::{JsonRpcRequest, JsonRpcResponse, McpServer};
+use crate::batch::{batch_tool, MAX_ITEMS};
+use crate::budget::Budget;
+use crate::contextpack::{merge_overlapping, pack, Candidate};
+use crate::cycles::{baseline_cycles, baseline_path, find_cycles, is_new_cycle};
+use crate::deadcode::{exclusion, Confidence, DeadCodeOptions};
+use crate::deps::{aggregate, DependencyOptions, Grouping, Level};
+use crate::duplicates::{cluster, normalize_tokens, token_similarity};
+use crate::entrypoints::{annotations, classify, EntryKind};
+use crate::export::{arguments_from_uri, render, Clustering, ExportFormat};
+use crate::filter::{fetch_filtered, is_test_path, SearchFilter};
+use crate::graph::{
+    call_hierarchy, call_paths, ego_graph, is_external_path, Boundaries, CallGraph, CallPathOptions, Direction, GraphNode,
+    HierarchyOptions, Subgraph,
+};
+use crate::grep::{build_regex, grep_text};
+use crate::navigation::{find_occurrences, word_at};
+use crate::neighbourhood::{mentioned_types, EdgeKind};
+use crate::outline::{outline, parents};
+use crate::query::parse;
+use crate::render::{Keys, OutputFormat, Render};
+use crate::repomap::{pagerank, render as render_repo_map, MapEntry};
+use crate::resolve::{choose, qualifier_score, split_path, ResolveError, SymbolKey};
+use crate::search::fuse_ranks;
+use crate::similar::{embedding_text, is_same_symbol};
+use crate::source::{content_hash, SourceFile};
+use crate::tokens::estimate_tokens;
+use crate::tree::{filter_tree, list_directory, TreeOptions};
+use crate::{percent_decode, query_arguments, JsonRpcRequest, JsonRpcResponse, McpServer};
 
 fn make_request(id: i64, method: &str, params: Option<Value>) -> JsonRpcRequest {
     JsonRpcRequest {
@@ -17,6 +45,24 @@ fn make_request(id: i64, method: &str, params: Option<Value>) -> JsonRpcRequest
     }
 }
 
+/// Symbol with id 1 and no location, for tests that build symbols by hand.
+fn test_symbol(name: &str, kind: adi_core::SymbolKind, visibility: adi_core::Visibility, path: &str) -> adi_core::Symbol {
+    adi_core::Symbol {
+        id: SymbolId(1),
+        name: name.to_string(),
+        kind,
+        file_id: adi_core::FileId(1),
+        file_path: PathBuf::from(path),
+        location: Default::default(),
+        parent_id: None,
+        signature: None,
+        description: None,
+        doc_comment: None,
+        visibility,
+        is_entry_point: false,
+    }
+}
+
 fn assert_success(response: &JsonRpcResponse) {
     assert!(response.error.is_none(), "Expected success but got error: {:?}", response.error);
     assert!(response.result.is_some(), "Expected result but got none");
@@ -27,6 +73,16 @@ fn assert_error(response: &JsonRpcResponse, expected_code: i32) {
     assert_eq!(response.error.as_ref().unwrap().code, expected_code);
 }
 
+/// Preloads a call graph with `caller -> callee` edges so no index lookups are needed.
+fn seed_call_graph(graph: &mut CallGraph, nodes: &[i64], edges: &[(i64, i64)]) {
+    for &node in nodes {
+        let callees = edges.iter().filter(|e| e.0 == node).map(|e| SymbolId(e.1)).collect();
+        let callers = edges.iter().filter(|e| e.1 == node).map(|e| SymbolId(e.0)).collect();
+        graph.insert_edges(SymbolId(node), Direction::Callees, callees);
+        graph.insert_edges(SymbolId(node), Direction::Callers, callers);
+    }
+}
+
 async fn create_test_project() -> (TempDir, PathBuf) {
     let temp_dir = TempDir::new().unwrap();
     let project_path = temp_dir.path().to_path_buf();
@@ -160,9 +216,24 @@ async fn test_tools_list() {
     assert!(tool_names.contains(&"search_files"));
     assert!(tool_names.contains(&"get_symbol"));
     assert!(tool_names.contains(&"get_file"));
+    assert!(tool_names.contains(&"grep"));
+    assert!(tool_names.contains(&"get_symbol_source"));
+    assert!(tool_names.contains(&"read_lines"));
+    assert!(tool_names.contains(&"symbol_at_position"));
+    assert!(tool_names.contains(&"goto_definition"));
+    assert!(tool_names.contains(&"find_references"));
     assert!(tool_names.contains(&"get_callers"));
     assert!(tool_names.contains(&"get_callees"));
     assert!(tool_names.contains(&"get_symbol_usage"));
+    assert!(tool_names.contains(&"get_call_hierarchy"));
+    assert!(tool_names.contains(&"find_call_paths"));
+    assert!(tool_names.contains(&"export_call_graph"));
+    assert!(tool_names.contains(&"get_dependency_graph"));
+    assert!(tool_names.contains(&"find_dependency_cycles"));
+    assert!(tool_names.contains(&"find_dead_code"));
+    assert!(tool_names.contains(&"find_entry_points"));
+    assert!(tool_names.contains(&"get_repo_map"));
+    assert!(tool_names.contains(&"build_context_pack"));
     assert!(tool_names.contains(&"get_tree"));
     assert!(tool_names.contains(&"index"));
     assert!(tool_names.contains(&"status"));
@@ -182,6 +253,10 @@ async fn test_tools_list_schema_format() {
         assert!(tool["description"].is_string());
         assert!(tool["inputSchema"].is_object());
         assert_eq!(tool["inputSchema"]["type"], "object");
+        if tool["name"] != "get_repo_map" && tool["name"] != "build_context_pack" {
+            assert!(tool["inputSchema"]["properties"]["max_tokens"].is_object());
+            assert!(tool["inputSchema"]["properties"]["cursor"].is_object());
+        }
     }
 }
 
@@ -245,6 +320,755 @@ async fn test_tools_call_unknown_tool() {
     assert_error(&response, -32602);
 }
 
+#[tokio::test]
+async fn test_read_lines_rejects_inverted_range() {
+    let (_temp_dir, project_path) = create_test_project().await;
+    let mut server = McpServer::new();
+
+    let init_request = make_request(
+        1,
+        "initialize",
+        Some(json!({ "rootUri": format!("file://{}", project_path.display()) })),
+    );
+    server.handle_request(init_request).await;
+
+    let request = make_request(
+        2,
+        "tools/call",
+        Some(json!({
+            "name": "read_lines",
+            "arguments": { "path": "src/main.rs", "start_line": 10, "end_line": 5 }
+        })),
+    );
+    let response = server.handle_request(request).await;
+
+    assert_error(&response, -32602);
+}
+
+// ==================== SOURCE TESTS ====================
+
+#[tokio::test]
+async fn test_source_span_numbers_and_clamps_lines() {
+    let (_temp_dir, project_path) = create_test_project().await;
+    let path = PathBuf::from("src/main.rs");
+    let file = SourceFile::read(&project_path, &path).unwrap();
+
+    let span = file.span(&path, 3, 5);
+    assert_eq!(span.start_line, 3);
+    assert_eq!(span.end_line, 5);
+    assert_eq!(span.content.lines().count(), 3);
+    assert!(span.content.starts_with("3 | fn main() {"));
+
+    let span = file.span(&path, 0, usize::MAX);
+    assert_eq!(span.start_line, 1);
+    assert_eq!(span.end_line, file.line_count());
+    assert_eq!(span.total_lines, file.line_count());
+
+    assert_eq!(file.lines(2, usize::MAX).lines().count(), file.line_count() - 1);
+}
+
+#[test]
+fn test_content_hash_detects_changes() {
+    assert_eq!(content_hash(b"fn main() {}"), content_hash(b"fn main() {}"));
+    assert_ne!(content_hash(b"fn main() {}"), content_hash(b"fn main() { }"));
+    assert_eq!(content_hash(b"").len(), 64);
+}
+
+#[tokio::test]
+async fn test_position_tools_require_one_based_position() {
+    let (_temp_dir, project_path) = create_test_project().await;
+    let mut server = McpServer::new();
+
+    let init_request = make_request(
+        1,
+        "initialize",
+        Some(json!({ "rootUri": format!("file://{}", project_path.display()) })),
+    );
+    server.handle_request(init_request).await;
+
+    for tool in ["symbol_at_position", "goto_definition", "find_references"] {
+        let request = make_request(
+            2,
+            "tools/call",
+            Some(json!({
+                "name": tool,
+                "arguments": { "path": "src/main.rs", "line": 0, "column": 1 }
+            })),
+        );
+        let response = server.handle_request(request).await;
+        assert_error(&response, -32602);
+    }
+}
+
+// ==================== NAVIGATION TESTS ====================
+
+#[test]
+fn test_word_at_cursor() {
+    let line = "    helper(value_1);";
+    assert_eq!(word_at(line, 5), Some(("helper".to_string(), 5, 10)));
+    assert_eq!(word_at(line, 8), Some(("helper".to_string(), 5, 10)));
+    assert_eq!(word_at(line, 14), Some(("value_1".to_string(), 12, 18)));
+    assert_eq!(word_at(line, 11), None);
+    assert_eq!(word_at(line, 0), None);
+    assert_eq!(word_at(line, 100), None);
+}
+
+#[test]
+fn test_find_occurrences_whole_words_in_range() {
+    let text = "fn main() {\n    helper();\n    helper_two();\n}\nfn x() { helper(); }\n";
+
+    assert_eq!(find_occurrences(text, "helper", 1, 4), vec![(2, 5)]);
+    assert_eq!(find_occurrences(text, "helper", 1, 5), vec![(2, 5), (5, 10)]);
+    assert!(find_occurrences(text, "helper", 5, 4).is_empty());
+}
+
+#[tokio::test]
+async fn test_symbol_tools_accept_names() {
+    let (_temp_dir, project_path) = create_test_project().await;
+    let mut server = McpServer::new();
+
+    let init_request = make_request(
+        1,
+        "initialize",
+        Some(json!({ "rootUri": format!("file://{}", project_path.display()) })),
+    );
+    server.handle_request(init_request).await;
+
+    for tool in ["get_symbol", "get_callers", "get_callees", "get_symbol_usage", "find_similar"] {
+        let request = make_request(
+            2,
+            "tools/call",
+            Some(json!({
+                "name": tool,
+                "arguments": { "id": "crate::no_such_module::no_such_symbol" }
+            })),
+        );
+        let response = server.handle_request(request).await;
+        assert_error(&response, -32602);
+        assert!(response.error.unwrap().message.contains("no_such_symbol"));
+    }
+}
+
+// ==================== RESOLVE TESTS ====================
+
+#[test]
+fn test_split_symbol_path() {
+    assert_eq!(split_path("crate::net::Client::connect"), vec!["net", "Client", "connect"]);
+    assert_eq!(split_path("Client.connect"), vec!["Client", "connect"]);
+    assert_eq!(split_path("self::helper"), vec!["helper"]);
+    assert_eq!(split_path("connect"), vec!["connect"]);
+}
+
+#[test]
+fn test_qualifier_score_uses_parents_and_modules() {
+    let parents = vec!["Client".to_string()];
+    let path = std::path::Path::new("src/net/mod.rs");
+
+    assert_eq!(qualifier_score(&["net", "Client"], &parents, path), 2);
+    assert_eq!(qualifier_score(&["storage", "Client"], &parents, path), 1);
+    assert_eq!(qualifier_score(&["mod"], &parents, path), 0);
+    assert_eq!(qualifier_score(&[], &parents, path), 0);
+}
+
+#[test]
+fn test_choose_requires_every_qualifier() {
+    use adi_core::{SymbolKind, Visibility};
+    let candidate = |score: usize| {
+        let symbol = test_symbol("parse", SymbolKind::Function, Visibility::Public, "src/foo.rs");
+        let candidate = crate::resolve::Candidate {
+            id: symbol.id,
+            name: "parse".to_string(),
+            qualified_name: "foo::parse".to_string(),
+            kind: "function".to_string(),
+            path: "src/foo.rs".to_string(),
+            line: 1,
+            score,
+        };
+        (symbol, candidate)
+    };
+
+    assert!(choose("parse", 0, vec![candidate(0)]).is_ok());
+    assert!(choose("foo::parse", 1, vec![candidate(1)]).is_ok());
+    match choose("other_mod::parse", 1, vec![candidate(0)]) {
+        Err(ResolveError::Ambiguous { candidates, .. }) => assert_eq!(candidates[0].qualified_name, "foo::parse"),
+        _ => panic!("a candidate missing a qualifier must not resolve"),
+    }
+    assert!(matches!(choose("parse", 0, vec![]), Err(ResolveError::NotFound(_))));
+}
+
+#[test]
+fn test_symbol_key_round_trip() {
+    let key = SymbolKey::parse("rust:src/net/client.rs#Client::connect").unwrap();
+    assert_eq!(key.language, "rust");
+    assert_eq!(key.path, "src/net/client.rs");
+    assert_eq!(key.qualified_name, "Client::connect");
+    assert_eq!(key.ordinal, 0);
+    assert_eq!(key.to_string(), "rust:src/net/client.rs#Client::connect");
+
+    let key = SymbolKey::parse("java:src/Foo.java#Foo::bar@2").unwrap();
+    assert_eq!(key.qualified_name, "Foo::bar");
+    assert_eq!(key.ordinal, 2);
+    assert_eq!(key.to_string(), "java:src/Foo.java#Foo::bar@2");
+}
+
+#[test]
+fn test_symbol_key_rejects_other_forms() {
+    assert!(SymbolKey::parse("src/net/client.rs#connect").is_none());
+    assert!(SymbolKey::parse("crate::net::connect").is_none());
+    assert!(SymbolKey::parse("rust:src/lib.rs#").is_none());
+    assert!(SymbolKey::parse("rust:src/lib.rs#f@x").is_none());
+}
+
+#[tokio::test]
+async fn test_symbol_resource_reports_deleted_key() {
+    let (_temp_dir, project_path) = create_test_project().await;
+    let mut server = McpServer::new();
+
+    let init_request = make_request(
+        1,
+        "initialize",
+        Some(json!({ "rootUri": format!("file://{}", project_path.display()) })),
+    );
+    server.handle_request(init_request).await;
+
+    let request = make_request(
+        2,
+        "resources/read",
+        Some(json!({ "uri": "adi://symbol/rust:src/gone.rs%23Gone::away" })),
+    );
+    let response = server.handle_request(request).await;
+
+    assert_error(&response, -32602);
+    let error = response.error.unwrap();
+    assert_eq!(error.data.unwrap()["status"], "deleted");
+    assert!(error.message.contains("rust:src/gone.rs#Gone::away"));
+}
+
+#[test]
+fn test_percent_decode() {
+    assert_eq!(percent_decode("rust:src/lib.rs%23Client%3A%3Anew"), "rust:src/lib.rs#Client::new");
+    assert_eq!(percent_decode("plain"), "plain");
+    assert_eq!(percent_decode("bad%zz"), "bad%zz");
+}
+
+// ==================== GREP TESTS ====================
+
+#[test]
+fn test_grep_literal_escapes_pattern() {
+    let regex = build_regex("x = {}", true, true).unwrap();
+    let hits = grep_text("let x = 1;\nprintln!(\"x = {}\", x);\n", &regex, 0, 10);
+
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].0, 2);
+    assert_eq!(hits[0].1, 11);
+}
+
+#[test]
+fn test_grep_case_insensitive_with_context() {
+    let regex = build_regex("^fn HELPER", false, false).unwrap();
+    let hits = grep_text("// a\n// b\nfn helper() {\n}\n", &regex, 1, 10);
+
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].0, 3);
+    assert_eq!(hits[0].2, vec!["// b".to_string()]);
+    assert_eq!(hits[0].3, vec!["}".to_string()]);
+
+    let hits = grep_text("// a\nfn helper() {\n}\n", &regex, usize::MAX, 10);
+    assert_eq!(hits[0].2.len(), 1);
+    assert_eq!(hits[0].3.len(), 1);
+}
+
+#[tokio::test]
+async fn test_grep_invalid_regex() {
+    let (_temp_dir, project_path) = create_test_project().await;
+    let mut server = McpServer::new();
+
+    let init_request = make_request(
+        1,
+        "initialize",
+        Some(json!({ "rootUri": format!("file://{}", project_path.display()) })),
+    );
+    server.handle_request(init_request).await;
+
+    let request = make_request(
+        2,
+        "tools/call",
+        Some(json!({
+            "name": "grep",
+            "arguments": { "pattern": "fn (", "regex": true }
+        })),
+    );
+    let response = server.handle_request(request).await;
+
+    assert_error(&response, -32602);
+}
+
+#[tokio::test]
+async fn test_search_tools_expose_filters() {
+    let mut server = McpServer::new();
+    let request = make_request(1, "tools/list", None);
+    let response = server.handle_request(request).await;
+
+    let tools = response.result.unwrap()["tools"].as_array().unwrap().clone();
+    for name in ["search", "search_symbols", "search_files"] {
+        let tool = tools.iter().find(|t| t["name"] == name).unwrap();
+        let properties = &tool["inputSchema"]["properties"];
+        for filter in ["language", "path_glob", "exclude_glob", "exclude_tests"] {
+            assert!(properties[filter].is_object(), "{} is missing {}", name, filter);
+        }
+    }
+    let search = tools.iter().find(|t| t["name"] == "search").unwrap();
+    assert!(search["inputSchema"]["properties"]["kind"].is_object());
+}
+
+#[tokio::test]
+async fn test_search_invalid_glob() {
+    let (_temp_dir, project_path) = create_test_project().await;
+    let mut server = McpServer::new();
+
+    let init_request = make_request(
+        1,
+        "initialize",
+        Some(json!({ "rootUri": format!("file://{}", project_path.display()) })),
+    );
+    server.handle_request(init_request).await;
+
+    let request = make_request(
+        2,
+        "tools/call",
+        Some(json!({
+            "name": "search_symbols",
+            "arguments": { "query": "helper", "path_glob": "src/[" }
+        })),
+    );
+    let response = server.handle_request(request).await;
+
+    assert_error(&response, -32602);
+}
+
+// ==================== FILTER TESTS ====================
+
+#[test]
+fn test_is_test_path() {
+    assert!(is_test_path(std::path::Path::new("tests/integration.rs")));
+    assert!(is_test_path(std::path::Path::new("pkg/server_test.go")));
+    assert!(is_test_path(std::path::Path::new("app/test_views.py")));
+    assert!(is_test_path(std::path::Path::new("web/button.spec.ts")));
+    assert!(!is_test_path(std::path::Path::new("src/latest.rs")));
+    assert!(!is_test_path(std::path::Path::new("services/auth/middleware.rs")));
+}
+
+#[test]
+fn test_filter_matches_path_globs() {
+    let filter = SearchFilter::from_arguments(&json!({
+        "path_glob": "services/**",
+        "exclude_glob": ["**/*.min.js"],
+        "exclude_tests": true
+    }))
+    .unwrap();
+
+    assert!(filter.matches_path(std::path::Path::new("services/auth/middleware.rs")));
+    assert!(!filter.matches_path(std::path::Path::new("web/auth.rs")));
+    assert!(!filter.matches_path(std::path::Path::new("services/bundle.min.js")));
+    assert!(!filter.matches_path(std::path::Path::new("services/tests/auth.rs")));
+}
+
+#[test]
+fn test_filter_matches_language_case_insensitive() {
+    let filter = SearchFilter::from_arguments(&json!({ "language": ["Rust", "go"] })).unwrap();
+    assert!(filter.matches_language("rust"));
+    assert!(filter.matches_language("go"));
+    assert!(!filter.matches_language("python"));
+
+    let filter = SearchFilter::from_arguments(&json!({})).unwrap();
+    assert!(filter.matches_language("python"));
+}
+
+#[tokio::test]
+async fn test_fetch_filtered_overfetches_to_fill_limit() {
+    let mut requested = Vec::new();
+    let results: Result<Vec<usize>, ()> = fetch_filtered(
+        5,
+        |n| {
+            requested.push(n);
+            std::future::ready(Ok((0..n.min(200)).collect()))
+        },
+        |v| v % 10 == 0,
+    )
+    .await;
+
+    assert_eq!(results.unwrap(), vec![0, 10, 20, 30, 40]);
+    assert!(requested.len() > 1);
+    assert_eq!(requested[0], 20);
+
+    let huge: Result<Vec<usize>, ()> = fetch_filtered(usize::MAX, |n| std::future::ready(Ok(vec![n])), |_| true).await;
+    assert_eq!(huge.unwrap(), vec![usize::MAX]);
+}
+
+#[tokio::test]
+async fn test_search_query_syntax_error_reports_position() {
+    let (_temp_dir, project_path) = create_test_project().await;
+    let mut server = McpServer::new();
+
+    let init_request = make_request(
+        1,
+        "initialize",
+        Some(json!({ "rootUri": format!("file://{}", project_path.display()) })),
+    );
+    server.handle_request(init_request).await;
+
+    let request = make_request(
+        2,
+        "tools/call",
+        Some(json!({
+            "name": "search",
+            "arguments": { "query": "kind:function \"retry backoff" }
+        })),
+    );
+    let response = server.handle_request(request).await;
+
+    assert_error(&response, -32602);
+    let error = response.error.unwrap();
+    assert!(error.message.contains("position 14"));
+    assert_eq!(error.data.unwrap()["position"], 14);
+}
+
+// ==================== QUERY SYNTAX TESTS ====================
+
+#[test]
+fn test_parse_query_terms_and_text() {
+    let parsed = parse(r#"kind:function lang:rust path:src/net/** name:conn* -path:tests "retry backoff""#).unwrap();
+
+    assert_eq!(parsed.text, "retry backoff");
+    let terms: Vec<_> = parsed.terms.iter().map(|t| (t.key, t.value.as_str(), t.negated)).collect();
+    assert_eq!(
+        terms,
+        vec![
+            ("kind", "function", false),
+            ("lang", "rust", false),
+            ("path", "src/net/**", false),
+            ("name", "conn*", false),
+            ("path", "tests", true),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_query_aliases_and_plain_text() {
+    let parsed = parse("language:Go std::io -verbose handler").unwrap();
+
+    assert_eq!(parsed.terms.len(), 1);
+    assert_eq!(parsed.terms[0].key, "lang");
+    assert_eq!(parsed.text, "std::io -verbose handler");
+}
+
+#[test]
+fn test_parse_query_name_only_searches_name_prefix() {
+    let parsed = parse("name:conn* kind:function").unwrap();
+    assert_eq!(parsed.search_text(), "conn");
+}
+
+#[test]
+fn test_parse_query_errors() {
+    let error = parse("kind:function \"retry").unwrap_err();
+    assert_eq!(error.position, 14);
+    assert!(error.message.contains("unterminated"));
+
+    let error = parse("lang: rust").unwrap_err();
+    assert_eq!(error.position, 5);
+
+    let error = parse("retry name:\"conn").unwrap_err();
+    assert_eq!(error.position, 11);
+}
+
+#[test]
+fn test_parse_query_unknown_keys_are_text() {
+    let parsed = parse("https://example.com/api error: timeout TODO: fix -colour:red kind:function").unwrap();
+
+    assert_eq!(parsed.text, "https://example.com/api error: timeout TODO: fix -colour:red");
+    assert_eq!(parsed.terms.len(), 1);
+    assert_eq!(parsed.terms[0].key, "kind");
+}
+
+#[test]
+fn test_filter_from_query_excludes_paths() {
+    let (text, filter) = SearchFilter::from_query(&json!({
+        "query": "-path:tests path:src/** retry"
+    }))
+    .unwrap();
+
+    assert_eq!(text, "retry");
+    assert!(filter.matches_path(std::path::Path::new("src/net/retry.rs")));
+    assert!(!filter.matches_path(std::path::Path::new("src/tests/retry.rs")));
+    assert!(!filter.matches_path(std::path::Path::new("lib/retry.rs")));
+}
+
+// ==================== HYBRID SEARCH TESTS ====================
+
+#[test]
+fn test_fuse_ranks_dedupes_and_rewards_agreement() {
+    let semantic = [SymbolId(1), SymbolId(2), SymbolId(3)];
+    let fts = [SymbolId(3), SymbolId(4)];
+    let fused = fuse_ranks(&semantic, &fts, &[], 0.5);
+
+    assert_eq!(fused.len(), 4);
+    assert_eq!(fused[0].0, SymbolId(3));
+    assert_eq!(fused[0].2.semantic_rank, Some(3));
+    assert_eq!(fused[0].2.fts_rank, Some(1));
+}
+
+#[test]
+fn test_fuse_ranks_exact_name_beats_semantic_head() {
+    let semantic = [SymbolId(1), SymbolId(2)];
+    let fts = [SymbolId(7)];
+    let fused = fuse_ranks(&semantic, &fts, &[SymbolId(7)], 0.8);
+
+    assert_eq!(fused[0].0, SymbolId(7));
+    assert!(fused[0].2.exact_name);
+}
+
+#[test]
+fn test_fuse_ranks_weight_extremes() {
+    let fused = fuse_ranks(&[SymbolId(1)], &[SymbolId(2)], &[], 1.0);
+    assert_eq!(fused[0].0, SymbolId(1));
+    assert_eq!(fused[1].1, 0.0);
+
+    let fused = fuse_ranks(&[SymbolId(1)], &[SymbolId(2)], &[], 0.0);
+    assert_eq!(fused[0].0, SymbolId(2));
+}
+
+// ==================== CALL GRAPH TESTS ====================
+
+#[tokio::test]
+async fn test_call_hierarchy_marks_cycles_and_shared_nodes() {
+    let (_temp_dir, project_path) = create_test_project().await;
+    let adi = adi_core::Adi::open(&project_path).await.unwrap();
+    let mut graph = CallGraph::new(&adi);
+    seed_call_graph(&mut graph, &[1, 2, 3], &[(1, 2), (1, 3), (2, 3), (3, 1)]);
+
+    let options = HierarchyOptions {
+        direction: Direction::Callees,
+        max_depth: 5,
+        max_nodes: 100,
+        boundaries: Boundaries::default(),
+    };
+    let hierarchy = call_hierarchy(&mut graph, SymbolId(1), &options);
+
+    assert_eq!(hierarchy.node_count, 3);
+    assert!(!hierarchy.truncated);
+    let root = &hierarchy.root;
+    assert_eq!(root.children.len(), 2);
+    let via_two = &root.children[0].children[0];
+    assert_eq!(via_two.node.id, SymbolId(3));
+    assert!(via_two.shared);
+    let direct = &root.children[1];
+    assert_eq!(direct.node.id, SymbolId(3));
+    assert!(direct.children[0].cycle);
+    assert_eq!(hierarchy.edges.iter().filter(|e| e.cycle).count(), 1);
+
+    let dag = hierarchy.into_dag();
+    assert_eq!(dag.nodes.len(), 3);
+    assert_eq!(dag.edges.len(), 4);
+}
+
+#[tokio::test]
+async fn test_call_hierarchy_expands_nodes_at_shallowest_depth() {
+    let (_temp_dir, project_path) = create_test_project().await;
+    let adi = adi_core::Adi::open(&project_path).await.unwrap();
+    let mut graph = CallGraph::new(&adi);
+    // 4 is reached first through the long path at the depth limit
+    seed_call_graph(&mut graph, &[1, 2, 3, 4, 5], &[(1, 2), (2, 3), (3, 4), (1, 4), (4, 5)]);
+
+    let options = HierarchyOptions {
+        direction: Direction::Callees,
+        max_depth: 3,
+        max_nodes: 100,
+        boundaries: Boundaries::default(),
+    };
+    let hierarchy = call_hierarchy(&mut graph, SymbolId(1), &options);
+
+    assert_eq!(hierarchy.node_count, 5);
+    assert!(!hierarchy.truncated);
+    let deep = &hierarchy.root.children[0].children[0].children[0];
+    assert_eq!(deep.node.id, SymbolId(4));
+    assert!(deep.shared);
+    assert!(!deep.truncated);
+    let shallow = &hierarchy.root.children[1];
+    assert_eq!(shallow.node.id, SymbolId(4));
+    assert!(!shallow.shared);
+    assert_eq!(shallow.children[0].node.id, SymbolId(5));
+
+    let dag = hierarchy.into_dag();
+    assert_eq!(dag.nodes.len(), 5);
+}
+
+#[tokio::test]
+async fn test_call_hierarchy_respects_depth_and_direction() {
+    let (_temp_dir, project_path) = create_test_project().await;
+    let adi = adi_core::Adi::open(&project_path).await.unwrap();
+    let mut graph = CallGraph::new(&adi);
+    seed_call_graph(&mut graph, &[1, 2, 3, 4], &[(1, 2), (2, 3), (3, 4)]);
+
+    let options = HierarchyOptions {
+        direction: Direction::Callers,
+        max_depth: 2,
+        max_nodes: 100,
+        boundaries: Boundaries::default(),
+    };
+    let hierarchy = call_hierarchy(&mut graph, SymbolId(4), &options);
+
+    assert!(hierarchy.truncated);
+    let level_two = &hierarchy.root.children[0].children[0];
+    assert_eq!(level_two.node.id, SymbolId(2));
+    assert!(level_two.truncated);
+    assert!(level_two.children.is_empty());
+    assert_eq!(hierarchy.edges[0].from, SymbolId(3));
+    assert_eq!(hierarchy.edges[0].to, SymbolId(4));
+}
+
+#[tokio::test]
+async fn test_call_hierarchy_rejects_unknown_shape() {
+    let (_temp_dir, project_path) = create_test_project().await;
+    let mut server = McpServer::new();
+
+    let init_request = make_request(
+        1,
+        "initialize",
+        Some(json!({ "rootUri": format!("file://{}", project_path.display()) })),
+    );
+    server.handle_request(init_request).await;
+
+    for shape in ["json", "Dag"] {
+        let request = make_request(
+            2,
+            "tools/call",
+            Some(json!({
+                "name": "get_call_hierarchy",
+                "arguments": { "id": 1, "shape": shape }
+            })),
+        );
+        let response = server.handle_request(request).await;
+
+        assert_error(&response, -32602);
+        assert!(response.error.unwrap().message.contains("Invalid shape"));
+    }
+}
+
+#[tokio::test]
+async fn test_call_paths_shortest_first() {
+    let (_temp_dir, project_path) = create_test_project().await;
+    let adi = adi_core::Adi::open(&project_path).await.unwrap();
+    let mut graph = CallGraph::new(&adi);
+    seed_call_graph(&mut graph, &[1, 2, 3, 4, 5], &[(1, 2), (2, 5), (1, 3), (3, 4), (4, 5), (5, 1)]);
+
+    let options = CallPathOptions { k: 3, max_depth: 10, max_expansions: 1000 };
+    let paths = call_paths(&mut graph, SymbolId(1), SymbolId(5), &options);
+    assert_eq!(paths.len(), 2);
+    assert_eq!(paths[0], vec![SymbolId(1), SymbolId(2), SymbolId(5)]);
+    assert_eq!(paths[1], vec![SymbolId(1), SymbolId(3), SymbolId(4), SymbolId(5)]);
+
+    let shallow = CallPathOptions { k: 3, max_depth: 2, max_expansions: 1000 };
+    assert_eq!(call_paths(&mut graph, SymbolId(1), SymbolId(5), &shallow).len(), 1);
+
+    let cycles = call_paths(&mut graph, SymbolId(1), SymbolId(1), &CallPathOptions { k: 1, ..options });
+    assert_eq!(cycles, vec![vec![SymbolId(1), SymbolId(2), SymbolId(5), SymbolId(1)]]);
+}
+
+#[tokio::test]
+async fn test_call_paths_not_connected() {
+    let (_temp_dir, project_path) = create_test_project().await;
+    let adi = adi_core::Adi::open(&project_path).await.unwrap();
+    let mut graph = CallGraph::new(&adi);
+    seed_call_graph(&mut graph, &[1, 2, 3], &[(1, 2)]);
+
+    let options = CallPathOptions { k: 1, max_depth: 10, max_expansions: 1000 };
+    assert!(call_paths(&mut graph, SymbolId(1), SymbolId(3), &options).is_empty());
+    assert!(call_paths(&mut graph, SymbolId(2), SymbolId(1), &options).is_empty());
+}
+
+#[tokio::test]
+async fn test_ego_graph_depth_and_edge_limit() {
+    let (_temp_dir, project_path) = create_test_project().await;
+    let adi = adi_core::Adi::open(&project_path).await.unwrap();
+    let mut graph = CallGraph::new(&adi);
+    seed_call_graph(&mut graph, &[1, 2, 3, 4, 5], &[(1, 2), (2, 3), (3, 4), (5, 1)]);
+
+    let subgraph = ego_graph(&mut graph, &[SymbolId(2)], None, 1, 100);
+    let ids: Vec<i64> = subgraph.nodes.iter().map(|n| n.id.0).collect();
+    assert_eq!(ids, vec![2, 3, 1]);
+    assert_eq!(subgraph.edges, vec![(SymbolId(2), SymbolId(3)), (SymbolId(1), SymbolId(2))]);
+    assert!(!subgraph.truncated);
+
+    let callees = ego_graph(&mut graph, &[SymbolId(1)], Some(Direction::Callees), 5, 2);
+    assert_eq!(callees.edges.len(), 2);
+    assert!(callees.truncated);
+}
+
+fn sample_subgraph() -> Subgraph {
+    let node = |id: i64, name: &str, path: &str| GraphNode {
+        id: SymbolId(id),
+        name: name.to_string(),
+        kind: "function".to_string(),
+        path: path.to_string(),
+        line: 1,
+    };
+    Subgraph {
+        nodes: vec![node(1, "main", "src/main.rs"), node(2, "parse<\"T\">", "src/util/parse.rs")],
+        seeds: [SymbolId(1)].into_iter().collect(),
+        edges: vec![(SymbolId(1), SymbolId(2))],
+        truncated: false,
+    }
+}
+
+#[test]
+fn test_render_call_graph_formats() {
+    let subgraph = sample_subgraph();
+
+    let dot = render(&subgraph, ExportFormat::Dot, Clustering::File);
+    assert!(dot.starts_with("digraph calls {"));
+    assert!(dot.contains("subgraph cluster_0 {\n    label=\"src/main.rs\";"));
+    assert!(dot.contains("n1 [label=\"main\", tooltip=\"src/main.rs:1\", style=bold];"));
+    assert!(dot.contains("label=\"parse<\\\"T\\\">\""));
+    assert!(dot.contains("  n1 -> n2;"));
+
+    let mermaid = render(&subgraph, ExportFormat::Mermaid, Clustering::Module);
+    assert!(mermaid.starts_with("flowchart LR\n"));
+    assert!(mermaid.contains("subgraph c0[\"src\"]"));
+    assert!(mermaid.contains("subgraph c1[\"src/util\"]"));
+    assert!(mermaid.contains("n2[\"parse<#quot;T#quot;>\"]"));
+    assert!(mermaid.contains("  n1 --> n2"));
+    assert!(mermaid.contains("class n1 seed"));
+
+    let graphml = render(&subgraph, ExportFormat::GraphMl, Clustering::None);
+    assert!(graphml.contains("<graph id=\"calls\" edgedefault=\"directed\">"));
+    assert!(graphml.contains("<data key=\"name\">parse&lt;&quot;T&quot;&gt;</data>"));
+    assert!(graphml.contains("<edge id=\"e0\" source=\"n1\" target=\"n2\"/>"));
+    assert!(!graphml.contains("key=\"cluster\">"));
+}
+
+#[test]
+fn test_call_graph_arguments_from_uri() {
+    let arguments = arguments_from_uri("dot/symbol/Client%3A%3Aconnect?depth=3&cluster=module").unwrap();
+    assert_eq!(arguments["format"], "dot");
+    assert_eq!(arguments["symbol"], "Client::connect");
+    assert_eq!(arguments["depth"], 3);
+    assert_eq!(arguments["cluster"], "module");
+
+    let arguments = arguments_from_uri("mermaid/dir/src/net").unwrap();
+    assert_eq!(arguments["directory"], "src/net");
+
+    assert!(arguments_from_uri("dot/package/x").is_none());
+    assert!(arguments_from_uri("").is_none());
+}
+
+#[test]
+fn test_is_external_path() {
+    assert!(is_external_path(std::path::Path::new("node_modules/react/index.js")));
+    assert!(is_external_path(std::path::Path::new("/usr/lib/rustlib/src/lib.rs")));
+    assert!(is_external_path(std::path::Path::new("")));
+    assert!(!is_external_path(std::path::Path::new("src/net/client.rs")));
+}
+
 // ==================== RESOURCES TESTS ====================
 
 #[tokio::test]
@@ -325,6 +1149,14 @@ async fn test_resources_read_unknown_uri() {
     let response = server.handle_request(request).await;
 
     assert_error(&response, -32602);
+
+    let request = make_request(3, "resources/read", Some(json!({ "uri": "adi://trees" })));
+    let response = server.handle_request(request).await;
+    assert_error(&response, -32602);
+
+    let request = make_request(4, "resources/read", Some(json!({ "uri": "adi://depsfoo" })));
+    let response = server.handle_request(request).await;
+    assert_error(&response, -32602);
 }
 
 #[tokio::test]
@@ -404,6 +1236,8 @@ async fn test_resource_templates_list() {
         .collect();
     assert!(uri_templates.contains(&"adi://file/{path}"));
     assert!(uri_templates.contains(&"adi://symbol/{id}"));
+    assert!(uri_templates.contains(&"adi://callgraph/{format}/symbol/{id}"));
+    assert!(uri_templates.contains(&"adi://deps/{path}"));
 }
 
 // ==================== PROMPTS TESTS ====================
@@ -679,3 +1513,796 @@ async fn test_response_null_id_when_missing() {
     let response = server.handle_request(request).await;
     assert_eq!(response.id, Value::Null);
 }
+
+// ==================== DEPENDENCY GRAPH TESTS ====================
+
+fn dependency_options(level: Level, scope: Option<&str>) -> DependencyOptions {
+    DependencyOptions {
+        level,
+        scope: scope.map(PathBuf::from),
+        depth: None,
+        min_weight: 1,
+    }
+}
+
+fn sample_references() -> (Vec<PathBuf>, Vec<(PathBuf, PathBuf)>) {
+    let files = ["api/handler.rs", "api/routes.rs", "storage/db.rs", "storage/cache.rs", "util.rs"]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+    let references = [
+        ("api/handler.rs", "storage/db.rs"),
+        ("api/handler.rs", "storage/db.rs"),
+        ("api/routes.rs", "api/handler.rs"),
+        ("storage/cache.rs", "storage/db.rs"),
+        ("storage/db.rs", "util.rs"),
+    ]
+    .iter()
+    .map(|(from, to)| (PathBuf::from(from), PathBuf::from(to)))
+    .collect();
+    (files, references)
+}
+
+#[test]
+fn test_dependency_graph_file_level() {
+    let (files, references) = sample_references();
+    let mut grouping = Grouping::new(std::path::Path::new("/nonexistent"), Level::File, None);
+    let graph = aggregate(&files, &references, &mut grouping, &dependency_options(Level::File, None));
+
+    assert_eq!(graph.nodes.len(), 5);
+    assert_eq!(graph.edges.len(), 4);
+    assert_eq!(graph.edges[0].from, "api/handler.rs");
+    assert_eq!(graph.edges[0].to, "storage/db.rs");
+    assert_eq!(graph.edges[0].weight, 2);
+
+    let db = graph.nodes.iter().find(|n| n.name == "storage/db.rs").unwrap();
+    assert_eq!(db.fan_in, 2);
+    assert_eq!(db.references_in, 3);
+    assert_eq!(db.fan_out, 1);
+}
+
+#[test]
+fn test_dependency_graph_directory_level_scoped() {
+    let (files, references) = sample_references();
+    let mut grouping = Grouping::new(std::path::Path::new("/nonexistent"), Level::Directory, None);
+    let graph = aggregate(&files, &references, &mut grouping, &dependency_options(Level::Directory, Some("storage")));
+
+    let edges: Vec<(&str, &str, usize)> = graph.edges.iter().map(|e| (e.from.as_str(), e.to.as_str(), e.weight)).collect();
+    assert_eq!(edges, vec![("api", "storage", 2), ("storage", ".", 1)]);
+
+    let storage = graph.nodes.iter().find(|n| n.name == "storage").unwrap();
+    assert_eq!(storage.files, 2);
+    assert_eq!(storage.fan_in, 1);
+    assert_eq!(storage.fan_out, 1);
+}
+
+#[test]
+fn test_dependency_grouping_by_package() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::create_dir_all(temp_dir.path().join("crates/core/src")).unwrap();
+    std::fs::write(temp_dir.path().join("crates/core/Cargo.toml"), "[package]").unwrap();
+
+    let mut grouping = Grouping::new(temp_dir.path(), Level::Package, None);
+    assert_eq!(grouping.group(std::path::Path::new("crates/core/src/lib.rs")), "crates/core");
+    assert_eq!(grouping.group(std::path::Path::new("tools/gen.rs")), ".");
+
+    let mut grouping = Grouping::new(temp_dir.path(), Level::Directory, Some(1));
+    assert_eq!(grouping.group(std::path::Path::new("crates/core/src/lib.rs")), "crates");
+}
+
+#[test]
+fn test_query_arguments() {
+    let arguments = query_arguments("level=package&path=src%2Fnet&flag");
+    assert_eq!(arguments["level"], "package");
+    assert_eq!(arguments["path"], "src/net");
+    assert_eq!(arguments["flag"], "");
+}
+
+// ==================== CYCLE TESTS ====================
+
+fn cycle_edges(edges: &[(&str, &str)]) -> Vec<(String, String, usize)> {
+    edges.iter().map(|(from, to)| (from.to_string(), to.to_string(), 1)).collect()
+}
+
+#[test]
+fn test_find_cycles_ranked_with_closing_edges() {
+    let edges = cycle_edges(&[("a", "b"), ("b", "c"), ("c", "a"), ("c", "d"), ("d", "e"), ("e", "d"), ("f", "f"), ("e", "g")]);
+    let components = find_cycles(&edges);
+
+    assert_eq!(components.len(), 2);
+    assert_eq!(components[0].nodes, vec!["a", "b", "c"]);
+    assert_eq!(components[0].edges.len(), 3);
+    assert_eq!(components[0].closing_edges, vec![("c".to_string(), "a".to_string(), 1)]);
+    assert_eq!(components[1].nodes, vec!["d", "e"]);
+    assert_eq!(components[1].closing_edges, vec![("e".to_string(), "d".to_string(), 1)]);
+
+    let acyclic = cycle_edges(&[("a", "b"), ("b", "c"), ("a", "c")]);
+    assert!(find_cycles(&acyclic).is_empty());
+}
+
+#[test]
+fn test_cycles_against_baseline() {
+    let edges = cycle_edges(&[("a", "b"), ("b", "a"), ("c", "d"), ("d", "c")]);
+    let cycles: Vec<_> = find_cycles(&edges).iter().map(|c| c.label(String::clone)).collect();
+
+    let saved = json!({ "cycles": [{ "size": 3, "nodes": ["a", "b", "x"] }] });
+    let baseline = baseline_cycles(&saved).unwrap();
+    assert!(!is_new_cycle(&cycles[0], &baseline));
+    assert!(is_new_cycle(&cycles[1], &baseline));
+
+    assert_eq!(baseline_cycles(&json!([["c", "d"]])).unwrap().len(), 1);
+    assert!(baseline_cycles(&json!({ "cycles": "none" })).is_none());
+}
+
+#[tokio::test]
+async fn test_baseline_path_stays_in_project() {
+    let (_temp_dir, project_path) = create_test_project().await;
+    std::fs::write(project_path.join("cycles.json"), "[]").unwrap();
+
+    let path = baseline_path(&project_path, "cycles.json").unwrap();
+    assert_eq!(path, project_path.canonicalize().unwrap().join("cycles.json"));
+    assert!(baseline_path(&project_path, "../cycles.json").is_err());
+    assert!(baseline_path(&project_path, "src/../../cycles.json").is_err());
+    assert!(baseline_path(&project_path, "/etc/passwd").is_err());
+    assert!(baseline_path(&project_path, "missing.json").is_err());
+}
+
+// ==================== DEAD CODE TESTS ====================
+
+fn dead_code_options() -> DeadCodeOptions {
+    DeadCodeOptions {
+        include_public: false,
+        ignore_names: None,
+        ignore_paths: None,
+        min_confidence: Confidence::Low,
+    }
+}
+
+#[test]
+fn test_dead_code_confidence() {
+    use adi_core::{SymbolKind, Visibility};
+
+    let private_fn = test_symbol("helper", SymbolKind::Function, Visibility::Private, "src/lib.rs");
+    assert_eq!(Confidence::of(&private_fn), Some(Confidence::High));
+    let crate_fn = test_symbol("helper", SymbolKind::Function, Visibility::PublicCrate, "src/lib.rs");
+    assert_eq!(Confidence::of(&crate_fn), Some(Confidence::Medium));
+    let public_fn = test_symbol("helper", SymbolKind::Function, Visibility::Public, "src/lib.rs");
+    assert_eq!(Confidence::of(&public_fn), Some(Confidence::Low));
+    let constant = test_symbol("LIMIT", SymbolKind::Constant, Visibility::Private, "src/lib.rs");
+    assert_eq!(Confidence::of(&constant), Some(Confidence::Low));
+    let field = test_symbol("name", SymbolKind::Field, Visibility::Private, "src/lib.rs");
+    assert_eq!(Confidence::of(&field), None);
+}
+
+#[test]
+fn test_dead_code_exclusions() {
+    use adi_core::{SymbolKind, Visibility};
+    let options = dead_code_options();
+
+    let main = test_symbol("main", SymbolKind::Function, Visibility::Private, "src/main.rs");
+    assert_eq!(exclusion(&main, None, &options), Some("entry_point"));
+    let test = test_symbol("it_works", SymbolKind::Function, Visibility::Private, "tests/api.rs");
+    assert_eq!(exclusion(&test, None, &options), Some("test"));
+    let public = test_symbol("connect", SymbolKind::Function, Visibility::Public, "src/lib.rs");
+    assert_eq!(exclusion(&public, None, &options), Some("public_api"));
+
+    let mut parent = test_symbol("Client", SymbolKind::Struct, Visibility::Public, "src/lib.rs");
+    parent.signature = Some("impl Display for Client".to_string());
+    let method = test_symbol("render", SymbolKind::Method, Visibility::Private, "src/lib.rs");
+    assert_eq!(exclusion(&method, Some(&parent), &options), Some("trait_impl"));
+    assert_eq!(exclusion(&method, None, &options), None);
+
+    let options = DeadCodeOptions {
+        include_public: true,
+        ignore_names: crate::filter::glob_argument(&json!({ "n": "handle_*" }), "n").unwrap(),
+        ..dead_code_options()
+    };
+    assert_eq!(exclusion(&public, None, &options), None);
+    let handler = test_symbol("handle_ping", SymbolKind::Function, Visibility::Private, "src/lib.rs");
+    assert_eq!(exclusion(&handler, None, &options), Some("ignored"));
+}
+
+// ==================== ENTRY POINT TESTS ====================
+
+#[test]
+fn test_entry_point_annotations() {
+    let lines = vec!["", "/// Lists users", "#[get(\"/users\")]", "async fn list_users() {}", "@app.task", "def cleanup(): pass"];
+    assert_eq!(annotations(&lines, 4), vec!["/// Lists users", "#[get(\"/users\")]"]);
+    assert_eq!(annotations(&lines, 6), vec!["@app.task"]);
+    assert_eq!(annotations(&lines, 5), vec!["@app.task"]);
+    assert!(annotations(&lines, 1).is_empty());
+}
+
+#[test]
+fn test_classify_entry_points() {
+    use adi_core::{SymbolKind, Visibility};
+    let function = |name: &str, path: &str| test_symbol(name, SymbolKind::Function, Visibility::Private, path);
+    let kind = |symbol: &adi_core::Symbol, annotations: &[&str]| classify(symbol, annotations).map(|(kind, _)| kind);
+
+    assert_eq!(kind(&function("main", "src/main.rs"), &[]), Some(EntryKind::Main));
+    assert_eq!(kind(&function("parses", "src/lib.rs"), &["#[tokio::test]"]), Some(EntryKind::Test));
+    assert_eq!(kind(&function("test_login", "app/auth.py"), &[]), Some(EntryKind::Test));
+    assert_eq!(kind(&function("list", "src/api.rs"), &["#[get(\"/users\")]"]), Some(EntryKind::HttpRoute));
+    assert_eq!(kind(&function("index", "app/views.py"), &["@router.get(\"/\")"]), Some(EntryKind::HttpRoute));
+    assert_eq!(kind(&function("report", "app/jobs.py"), &["@shared_task"]), Some(EntryKind::ScheduledJob));
+    assert_eq!(kind(&function("nightly_job", "src/jobs.rs"), &[]), Some(EntryKind::ScheduledJob));
+    assert_eq!(kind(&function("deploy", "cli.py"), &["@click.command()"]), Some(EntryKind::CliCommand));
+    assert_eq!(kind(&function("helper", "src/util.rs"), &["// just a comment"]), None);
+
+    let exported = test_symbol("Client", SymbolKind::Struct, Visibility::Public, "src/lib.rs");
+    assert_eq!(kind(&exported, &[]), Some(EntryKind::LibraryRoot));
+    let internal = test_symbol("Client", SymbolKind::Struct, Visibility::Public, "src/client.rs");
+    assert_eq!(kind(&internal, &[]), None);
+}
+
+// ==================== REPO MAP TESTS ====================
+
+#[test]
+fn test_estimate_tokens() {
+    assert_eq!(estimate_tokens(""), 0);
+    assert_eq!(estimate_tokens("hello"), 2);
+    assert!(estimate_tokens("fn connect(addr: &str) -> Result<Client, Error>") >= 10);
+    assert!(estimate_tokens(&"word ".repeat(100)) >= 100);
+}
+
+#[test]
+fn test_pagerank_favours_called_symbols() {
+    let nodes: Vec<SymbolId> = (1..=4).map(SymbolId).collect();
+    let edges = vec![(SymbolId(1), SymbolId(3), 1), (SymbolId(2), SymbolId(3), 1), (SymbolId(4), SymbolId(3), 1), (SymbolId(3), SymbolId(4), 1)];
+
+    let ranks = pagerank(&nodes, &edges, &Default::default());
+    let total: f64 = ranks.values().sum();
+    assert!((total - 1.0).abs() < 1e-6);
+    assert!(ranks[&SymbolId(3)] > ranks[&SymbolId(4)]);
+    assert!(ranks[&SymbolId(4)] > ranks[&SymbolId(1)]);
+
+    let seeded = pagerank(&nodes, &edges, &[SymbolId(1)].into_iter().collect());
+    assert!(seeded[&SymbolId(1)] > ranks[&SymbolId(1)]);
+    assert_eq!(seeded[&SymbolId(2)], 0.0);
+}
+
+#[test]
+fn test_repo_map_respects_budget() {
+    let entry = |id: i64, path: &str, line: u32, rank: f64| MapEntry {
+        id: SymbolId(id),
+        path: PathBuf::from(path),
+        line,
+        text: format!("fn symbol_number_{}()", id),
+        rank,
+    };
+    let entries = vec![entry(1, "src/a.rs", 10, 0.5), entry(2, "src/b.rs", 3, 0.3), entry(3, "src/a.rs", 2, 0.1), entry(4, "src/c.rs", 1, 0.05)];
+
+    let full = render_repo_map(entries.clone(), 10_000);
+    assert_eq!(full.symbols, 4);
+    assert!(full.text.starts_with("src/a.rs:\n    2│ fn symbol_number_3()\n   10│ fn symbol_number_1()\n"));
+
+    let small = render_repo_map(entries, 20);
+    assert!(small.tokens <= 20);
+    assert!(small.text.contains("symbol_number_1"));
+    assert!(!small.text.contains("symbol_number_4"));
+}
+
+// ==================== CONTEXT PACK TESTS ====================
+
+fn pack_candidate(path: &str, start_line: usize, end_line: usize, priority: f64, name: &str) -> Candidate {
+    Candidate {
+        path: PathBuf::from(path),
+        start_line,
+        end_line,
+        priority,
+        symbols: vec![name.to_string()],
+        reasons: vec![format!("reason for {}", name)],
+    }
+}
+
+#[test]
+fn test_context_pack_merges_overlapping_ranges() {
+    let merged = merge_overlapping(vec![
+        pack_candidate("src/a.rs", 10, 30, 0.4, "outer"),
+        pack_candidate("src/a.rs", 12, 15, 0.9, "inner"),
+        pack_candidate("src/a.rs", 40, 45, 0.2, "later"),
+        pack_candidate("src/b.rs", 12, 15, 0.5, "other"),
+    ]);
+
+    assert_eq!(merged.len(), 3);
+    assert_eq!(merged[0].symbols, vec!["outer", "inner"]);
+    assert_eq!((merged[0].start_line, merged[0].end_line), (10, 30));
+    assert_eq!(merged[0].priority, 0.9);
+    assert_eq!(merged[0].reasons.len(), 2);
+    assert_eq!(merged[1].path, PathBuf::from("src/b.rs"));
+}
+
+#[test]
+fn test_context_pack_fits_budget() {
+    let text: String = (1..=200).map(|i| format!("let value_{} = compute_something({});\n", i, i)).collect();
+    let read = |_: &std::path::Path| Some(SourceFile { text: text.clone(), hash: "h".to_string() });
+    let candidates = vec![
+        pack_candidate("src/a.rs", 1, 5, 0.9, "small"),
+        pack_candidate("src/a.rs", 10, 200, 0.8, "huge"),
+        pack_candidate("src/b.rs", 1, 3, 0.1, "late"),
+    ];
+
+    let result = pack("task", candidates, 120, read);
+    assert!(result.used_tokens <= 120);
+    assert_eq!(result.snippets[0].symbols, vec!["small"]);
+    assert_eq!(result.snippets[0].reason, "reason for small");
+    assert!(result.snippets[1].truncated);
+    assert!(result.snippets[1].end_line < 200);
+    assert_eq!(result.snippets.len() + result.omitted, 3);
+}
+
+// ==================== TOKEN BUDGET TESTS ====================
+
+#[test]
+fn test_budget_from_arguments() {
+    assert!(Budget::from_arguments(&json!({})).unwrap().is_none());
+    let budget = Budget::from_arguments(&json!({ "max_tokens": 100, "cursor": "20" })).unwrap().unwrap();
+    assert_eq!((budget.max_tokens, budget.offset), (100, 20));
+    assert!(Budget::from_arguments(&json!({ "max_tokens": 0 })).is_err());
+    assert!(Budget::from_arguments(&json!({ "max_tokens": 10, "cursor": "abc" })).is_err());
+}
+
+#[test]
+fn test_budget_pages_json_lists() {
+    let items: Vec<Value> = (0..50).map(|i| json!({ "id": i, "name": format!("symbol_{}", i), "kind": "function" })).collect();
+    let output = json!({ "query": "x", "results": items });
+
+    let first = Budget { max_tokens: 100, offset: 0 }.apply_json(output.clone());
+    let returned = first["budget"]["returned"].as_u64().unwrap() as usize;
+    assert!(returned > 0 && returned < 50);
+    assert_eq!(first["results"].as_array().unwrap().len(), returned);
+    assert_eq!(first["budget"]["dropped_items"].as_u64().unwrap() as usize, 50 - returned);
+    assert!(first["budget"]["dropped_tokens"].as_u64().unwrap() > 0);
+    assert_eq!(first["query"], "x");
+
+    let cursor: usize = first["budget"]["next_cursor"].as_str().unwrap().parse().unwrap();
+    let second = Budget { max_tokens: 100, offset: cursor }.apply_json(output.clone());
+    assert_eq!(second["results"][0]["id"], returned);
+
+    let last = Budget { max_tokens: 100_000, offset: 45 }.apply_json(json!(output["results"]));
+    assert_eq!(last["items"].as_array().unwrap().len(), 5);
+    assert!(last["budget"]["next_cursor"].is_null());
+}
+
+#[test]
+fn test_budget_cuts_text_by_lines() {
+    let text: String = (1..=100).map(|i| format!("line number {}\n", i)).collect();
+    let budget = Budget { max_tokens: 30, offset: 0 };
+
+    let fitted = budget.apply(&text);
+    assert!(fitted.starts_with("line number 1\n"));
+    assert!(fitted.contains("[truncated: "));
+    assert!(fitted.lines().count() < 100);
+
+    let single = json!({ "id": 1, "name": "small" }).to_string();
+    assert_eq!(budget.apply(&single), single);
+}
+
+#[test]
+fn test_budget_pages_json_text_fields() {
+    let source: String = (1..=200).map(|i| format!("    let value_{} = compute({});\n", i, i)).collect();
+    let output = json!({ "id": 7, "path": "src/lib.rs", "source": source }).to_string();
+
+    let fitted = Budget { max_tokens: 200, offset: 0 }.apply(&output);
+    let first: Value = serde_json::from_str(&fitted).unwrap();
+    assert_eq!(first["id"], 7);
+    assert!(first["source"].as_str().unwrap().starts_with("    let value_1 = compute(1);\n"));
+    let returned = first["budget"]["returned"].as_u64().unwrap();
+    assert!(returned > 0 && returned < 200);
+    assert!(first["budget"]["used_tokens"].as_u64().unwrap() <= 200);
+
+    let cursor = first["budget"]["next_cursor"].as_str().unwrap();
+    let second: Value =
+        serde_json::from_str(&Budget { max_tokens: 200, offset: cursor.parse().unwrap() }.apply(&output)).unwrap();
+    let expected = format!("    let value_{} = compute(", returned + 1);
+    assert!(second["source"].as_str().unwrap().starts_with(&expected));
+}
+
+#[test]
+fn test_budget_cuts_nested_fields() {
+    fn tree(depth: usize) -> Value {
+        let children: Vec<Value> = if depth == 0 { vec![] } else { (0..3).map(|_| tree(depth - 1)).collect() };
+        json!({ "name": "node_with_a_long_name", "children": children })
+    }
+    let output = json!({ "root": tree(5), "edges": [[1, 2], [2, 3]] });
+
+    let fitted = Budget { max_tokens: 300, offset: 0 }.apply_json(output);
+    assert!(fitted["budget"]["used_tokens"].as_u64().unwrap() <= 300);
+    assert!(fitted["budget"]["over_budget"].is_null());
+    let truncated = fitted["budget"]["truncated_fields"].as_array().unwrap();
+    assert!(truncated.iter().any(|p| p.as_str().unwrap().starts_with("/root")));
+}
+
+#[test]
+fn test_budget_file_resource_over_budget() {
+    // Shape of an adi://file/{path} resource
+    let content: String = (1..=500).map(|i| format!("pub fn function_{}() -> usize {{ {} }}\n", i, i)).collect();
+    let symbols: Vec<Value> = (1..=20).map(|i| json!({ "id": i, "name": format!("function_{}", i) })).collect();
+    let resource = json!({
+        "file": { "path": "src/lib.rs", "language": "rust" },
+        "symbols": symbols,
+        "content": content
+    })
+    .to_string();
+
+    let fitted = Budget { max_tokens: 500, offset: 0 }.apply(&resource);
+    let fitted: Value = serde_json::from_str(&fitted).expect("budgeted resource stays valid JSON");
+    assert!(fitted["content"].as_str().unwrap().starts_with("pub fn function_1()"));
+    assert_eq!(fitted["file"]["path"], "src/lib.rs");
+    assert!(fitted["budget"]["next_cursor"].is_string());
+    assert!(fitted["budget"]["used_tokens"].as_u64().unwrap() <= 500);
+}
+
+// ==================== OUTPUT FORMAT TESTS ====================
+
+#[test]
+fn test_output_format_client_default() {
+    let config = "claude-code=compact, Cursor=markdown, json";
+    assert_eq!(OutputFormat::client_default(config, Some("claude-code")), Some(OutputFormat::Compact));
+    assert_eq!(OutputFormat::client_default(config, Some("cursor")), Some(OutputFormat::Markdown));
+    assert_eq!(OutputFormat::client_default(config, Some("other")), Some(OutputFormat::Json));
+    assert_eq!(OutputFormat::client_default("compact", None), Some(OutputFormat::Compact));
+    assert_eq!(OutputFormat::client_default("cursor=markdown", Some("zed")), None);
+    assert_eq!(OutputFormat::client_default("cursor=mdx, compact", Some("cursor")), Some(OutputFormat::Compact));
+
+    assert_eq!(OutputFormat::from_arguments(&json!({}), OutputFormat::Compact).unwrap(), OutputFormat::Compact);
+    assert_eq!(OutputFormat::from_arguments(&json!({ "format": "markdown" }), OutputFormat::Json).unwrap(), OutputFormat::Markdown);
+    assert!(OutputFormat::from_arguments(&json!({ "format": "yaml" }), OutputFormat::Json).is_err());
+}
+
+#[tokio::test]
+async fn test_compact_symbol_lines() {
+    use adi_core::{SymbolKind, Visibility};
+    let mut symbol = test_symbol("connect", SymbolKind::Function, Visibility::Public, "src/net.rs");
+    symbol.id = SymbolId(7);
+    symbol.location.start_line = 42;
+    symbol.signature = Some("pub fn connect(addr: &str) -> Result<Conn> {\n    todo!()\n}".to_string());
+    let plain = test_symbol("helper", SymbolKind::Function, Visibility::Private, "src/util.rs");
+
+    let symbols = vec![symbol.clone(), plain];
+    let compact = symbols.as_slice().compact(&mut Keys::default());
+    let lines: Vec<&str> = compact.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("#7 src/net.rs:42 "));
+    assert!(lines[0].ends_with("pub fn connect(addr: &str) -> Result<Conn>"));
+    assert!(lines[1].ends_with(" helper"));
+
+    let markdown = symbols.as_slice().markdown(&mut Keys::default());
+    assert!(markdown.starts_with("- **connect**"));
+    assert!(markdown.contains("#7"));
+    assert!(!compact.contains('{') && !compact.contains('"'));
+    assert!(symbol.markdown(&mut Keys::default()).contains("```\npub fn connect"));
+
+    let (_temp_dir, project_path) = create_test_project().await;
+    let adi = adi_core::Adi::open(&project_path).await.unwrap();
+    let keyed = symbols.as_slice().compact(&mut Keys::new(&adi));
+    assert!(keyed.lines().next().unwrap().ends_with("Result<Conn> (unknown:src/net.rs#connect)"));
+    assert!(symbol.markdown(&mut Keys::new(&adi)).contains("- **Key:** `unknown:src/net.rs#connect`"));
+}
+
+#[tokio::test]
+async fn test_tools_list_format_property() {
+    let mut server = McpServer::new();
+    let response = server.handle_request(make_request(1, "tools/list", None)).await;
+    let tools = response.result.unwrap()["tools"].as_array().unwrap().clone();
+    let schema = |name: &str| tools.iter().find(|t| t["name"] == name).unwrap()["inputSchema"]["properties"].clone();
+
+    assert_eq!(schema("search")["format"]["enum"], json!(["json", "markdown", "compact"]));
+    assert!(schema("status")["format"].is_object());
+    // Tools with their own format keep it
+    assert_ne!(schema("export_call_graph")["format"]["enum"], json!(["json", "markdown", "compact"]));
+}
+
+// ==================== TREE TESTS ====================
+
+fn tree_file(path: &str, language: adi_core::Language, symbols: usize) -> adi_core::FileNode {
+    adi_core::FileNode {
+        path: PathBuf::from(path),
+        language,
+        description: None,
+        symbols: (0..symbols)
+            .map(|i| adi_core::SymbolNode {
+                id: SymbolId(i as i64),
+                name: format!("item{}", i),
+                kind: adi_core::SymbolKind::Function,
+                location: Default::default(),
+                children: vec![],
+            })
+            .collect(),
+    }
+}
+
+fn sample_tree() -> adi_core::Tree {
+    use adi_core::Language;
+    adi_core::Tree {
+        files: vec![
+            tree_file("README.md", Language::Markdown, 0),
+            tree_file("src/main.rs", Language::Rust, 2),
+            tree_file("src/net/conn.rs", Language::Rust, 3),
+            tree_file("src/net/tls/mod.rs", Language::Rust, 1),
+            tree_file("scripts/build.py", Language::Python, 1),
+        ],
+    }
+}
+
+#[test]
+fn test_tree_options_from_query_values() {
+    let options = TreeOptions::from_arguments(&json!({
+        "path": "./src/",
+        "depth": 1,
+        "kind": "Struct,trait",
+        "symbols": "false"
+    }))
+    .unwrap();
+    assert_eq!(options.path, Some(PathBuf::from("src")));
+    assert_eq!(options.max_depth, Some(1));
+    assert_eq!(options.kinds, vec!["struct", "trait"]);
+    assert!(!options.symbols);
+    assert!(TreeOptions::from_arguments(&json!({ "symbols": "maybe" })).is_err());
+    let error = TreeOptions::from_arguments(&json!({ "kind": "struct,widget" })).unwrap_err();
+    assert_eq!(error.code, -32602);
+    assert!(error.message.contains("widget"));
+    assert_eq!(TreeOptions::from_arguments(&json!({ "path": "." })).unwrap().path, None);
+}
+
+#[test]
+fn test_filter_tree_subtree_depth_and_symbols() {
+    let options = TreeOptions {
+        path: Some(PathBuf::from("src")),
+        max_depth: Some(1),
+        symbols: false,
+        ..Default::default()
+    };
+    let tree = filter_tree(sample_tree(), &options);
+
+    let listed: Vec<PathBuf> = tree.files.iter().map(|f| f.path.clone()).collect();
+    assert_eq!(listed, vec![PathBuf::from("src/main.rs"), PathBuf::from("src/net/conn.rs")]);
+    assert!(tree.files.iter().all(|f| f.symbols.is_empty()));
+    assert_eq!(tree.collapsed.len(), 1);
+    assert_eq!(tree.collapsed[0].path, "src/net/tls");
+    assert_eq!((tree.collapsed[0].files, tree.collapsed[0].symbols), (1, 1));
+}
+
+#[test]
+fn test_filter_tree_collapses_large_directories() {
+    let options = TreeOptions {
+        collapse_above: Some(2),
+        symbols: true,
+        ..Default::default()
+    };
+    let tree = filter_tree(sample_tree(), &options);
+
+    let listed: Vec<PathBuf> = tree.files.iter().map(|f| f.path.clone()).collect();
+    assert_eq!(listed, vec![PathBuf::from("README.md"), PathBuf::from("scripts/build.py")]);
+    assert_eq!(tree.collapsed.len(), 1);
+    assert_eq!(tree.collapsed[0].path, "src");
+    assert_eq!((tree.collapsed[0].files, tree.collapsed[0].symbols), (3, 6));
+}
+
+#[test]
+fn test_list_directory_children() {
+    let listing = list_directory(&sample_tree(), &TreeOptions::default());
+    let directories: Vec<&str> = listing.directories.iter().map(|d| d.path.as_str()).collect();
+    assert_eq!(directories, vec!["scripts", "src"]);
+    assert_eq!(listing.directories[1].files, 3);
+    assert_eq!(listing.files.len(), 1);
+    assert_eq!(listing.files[0].path, "README.md");
+
+    let nested = list_directory(&sample_tree(), &TreeOptions { path: Some(PathBuf::from("src")), ..Default::default() });
+    assert_eq!(nested.directories.len(), 1);
+    assert_eq!(nested.directories[0].path, "src/net");
+    assert_eq!(nested.files[0].symbols, 2);
+}
+
+// ==================== SIMILARITY TESTS ====================
+
+#[test]
+fn test_embedding_text_combines_signature_doc_and_body() {
+    use adi_core::{SymbolKind, Visibility};
+    let mut symbol = test_symbol("retry", SymbolKind::Function, Visibility::Public, "src/net.rs");
+    assert_eq!(embedding_text(&symbol, None), format!("{} retry", SymbolKind::Function.as_str()));
+
+    symbol.signature = Some("fn retry(times: u32)".to_string());
+    symbol.doc_comment = Some("  Retries with backoff.  ".to_string());
+    let text = embedding_text(&symbol, Some("loop { attempt() }"));
+    assert_eq!(text, "fn retry(times: u32)\nRetries with backoff.\nloop { attempt() }");
+
+    let long = "x".repeat(5000);
+    assert_eq!(embedding_text(&symbol, Some(&long)).chars().count(), 2000);
+}
+
+#[test]
+fn test_is_same_symbol_survives_reindexing() {
+    use adi_core::{SymbolKind, Visibility};
+    let target = test_symbol("retry", SymbolKind::Function, Visibility::Public, "src/net.rs");
+    let mut reindexed = target.clone();
+    reindexed.id = SymbolId(99);
+    assert!(is_same_symbol(&target, &reindexed));
+
+    let mut other = reindexed.clone();
+    other.location.start_line = 40;
+    assert!(!is_same_symbol(&target, &other));
+}
+
+#[test]
+fn test_source_lines_are_raw() {
+    let file = SourceFile {
+        text: "a\nb\nc\nd".to_string(),
+        hash: String::new(),
+    };
+    assert_eq!(file.lines(2, 3), "b\nc");
+    assert_eq!(file.lines(0, 1), "a");
+    assert_eq!(file.lines(3, 10), "c\nd");
+}
+
+// ==================== DUPLICATE TESTS ====================
+
+#[test]
+fn test_normalize_tokens_ignores_names_literals_and_comments() {
+    let a = "fn total(items: &[Item]) -> u64 {\n    // sum prices\n    items.iter().map(|i| i.price * 2).sum()\n}";
+    let b = "fn sum_all(xs: &[Entry]) -> u64 {\n    xs.iter().map(|x| x.cost * 3).sum() // doubled\n}";
+    assert_eq!(normalize_tokens(a), normalize_tokens(b));
+    assert_eq!(token_similarity(&normalize_tokens(a), &normalize_tokens(b)), 1.0);
+
+    assert_eq!(normalize_tokens(r#"log("a \"quoted\" b", 'c')"#), vec!["$", "(", "\"\"", ",", "\"\"", ")"]);
+    let different = normalize_tokens("if ready { return None; } while busy { wait(); }");
+    assert!(token_similarity(&normalize_tokens(a), &different) < 0.2);
+}
+
+#[test]
+fn test_normalize_tokens_rust_lifetimes() {
+    let tokens = normalize_tokens("fn longest<'a>(x: &'a str, y: &'a str) -> &'a str { if x.len() > y.len() { x } else { y } }");
+    assert_eq!(tokens[..6], ["fn", "$", "<", "'", "$", ">"]);
+    assert!(tokens.contains(&"if".to_string()) && tokens.contains(&"else".to_string()));
+    assert!(!tokens.contains(&"\"\"".to_string()));
+
+    let tokens = normalize_tokens("impl<'a, 'b> Pair<'a, 'b> { fn get(&self) -> &'static str { 'outer: loop { break 'outer; } } }");
+    assert!(!tokens.contains(&"\"\"".to_string()));
+    assert!(tokens.contains(&"loop".to_string()) && tokens.contains(&"break".to_string()));
+
+    assert_eq!(normalize_tokens("let c = 'x'; let n = '\\n';").iter().filter(|t| *t == "\"\"").count(), 2);
+    assert_eq!(normalize_tokens("print('hello world')"), vec!["$", "(", "\"\"", ")"]);
+}
+
+#[test]
+fn test_cluster_connected_pairs() {
+    let clusters = cluster(6, &[(0, 1), (4, 1), (2, 5)]);
+    assert_eq!(clusters, vec![vec![0, 1, 4], vec![2, 5]]);
+    assert!(cluster(3, &[]).is_empty());
+}
+
+// ==================== NEIGHBOURHOOD TESTS ====================
+
+#[test]
+fn test_mentioned_types_skips_builtins_and_self() {
+    let signature = "pub fn open(config: &Config, pool: Arc<Pool>) -> Result<Connection, DbError>";
+    assert_eq!(mentioned_types(signature, "open"), vec!["Config", "Pool", "Connection", "DbError"]);
+    assert_eq!(mentioned_types("impl Connection for Connection", "Connection"), Vec::<String>::new());
+    assert_eq!(mentioned_types("def load(path: Path) -> Optional[Document]:", "load"), vec!["Path", "Document"]);
+}
+
+#[test]
+fn test_edge_kind_parse() {
+    for kind in EdgeKind::ALL {
+        let name = json!(kind);
+        assert_eq!(EdgeKind::parse(name.as_str().unwrap()), Some(kind));
+    }
+    assert_eq!(EdgeKind::parse("imports"), None);
+}
+
+#[tokio::test]
+async fn test_neighbourhood_requires_seeds() {
+    let (_temp_dir, project_path) = create_test_project().await;
+    let mut server = McpServer::new();
+    server
+        .handle_request(make_request(1, "initialize", Some(json!({ "rootUri": format!("file://{}", project_path.display()) }))))
+        .await;
+
+    let response = server
+        .handle_request(make_request(2, "tools/call", Some(json!({ "name": "get_neighbourhood", "arguments": { "ids": [] } }))))
+        .await;
+    assert_error(&response, -32602);
+    assert!(response.error.unwrap().message.contains("ids"));
+}
+
+// ==================== BATCH TESTS ====================
+
+#[test]
+fn test_batch_items_share_arguments() {
+    let batch = batch_tool("get_symbol_sources").unwrap();
+    assert_eq!(batch.tool, "get_symbol_source");
+    let items = batch.items(&json!({ "ids": [7, "main"], "context_before": 2 })).unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].0, json!(7));
+    assert_eq!(items[1].1, json!({ "id": "main", "context_before": 2, "format": "json" }));
+
+    let items = batch_tool("get_symbols").unwrap().items(&json!({ "ids": [1], "format": "compact" })).unwrap();
+    assert_eq!(items[0].1["format"], "json");
+
+    assert!(batch.items(&json!({ "ids": [] })).is_err());
+    assert!(batch.items(&json!({ "ids": vec![1; MAX_ITEMS + 1] })).is_err());
+    assert!(batch_tool("get_tree").is_none());
+}
+
+#[tokio::test]
+async fn test_batch_reports_per_item_errors() {
+    let (_temp_dir, project_path) = create_test_project().await;
+    let mut server = McpServer::new();
+    server
+        .handle_request(make_request(1, "initialize", Some(json!({ "rootUri": format!("file://{}", project_path.display()) }))))
+        .await;
+
+    for (tool, key) in [("get_symbols", "ids"), ("get_symbol_usages", "ids"), ("get_files", "paths")] {
+        let request = make_request(
+            2,
+            "tools/call",
+            Some(json!({ "name": tool, "arguments": { key: ["crate::no_such_symbol", "missing.rs"] } })),
+        );
+        let response = server.handle_request(request).await;
+        assert_success(&response);
+
+        let text = response.result.unwrap()["content"][0]["text"].as_str().unwrap().to_string();
+        let output: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(output["count"], 2);
+        assert_eq!(output["failed"], 2);
+        assert_eq!(output["items"][0]["input"], "crate::no_such_symbol");
+        assert!(output["items"][1]["error"]["message"].is_string());
+    }
+}
+
+// ==================== OUTLINE TESTS ====================
+
+fn outline_symbol(id: i64, name: &str, kind: adi_core::SymbolKind, lines: (u32, u32), parent: Option<i64>) -> adi_core::Symbol {
+    let mut symbol = test_symbol(name, kind, adi_core::Visibility::Public, "src/conn.rs");
+    symbol.id = SymbolId(id);
+    symbol.location.start_line = lines.0;
+    symbol.location.end_line = lines.1;
+    symbol.parent_id = parent.map(SymbolId);
+    symbol
+}
+
+#[test]
+fn test_outline_nests_by_parent_and_range() {
+    use adi_core::SymbolKind;
+    let mut open = outline_symbol(3, "open", SymbolKind::Method, (12, 20), Some(2));
+    open.signature = Some("pub fn open(addr: &str) -> Self {".to_string());
+    open.doc_comment = Some("\nOpens a connection.\nMore detail.".to_string());
+    let symbols = vec![
+        outline_symbol(5, "helper", SymbolKind::Function, (30, 35), None),
+        open,
+        outline_symbol(2, "Conn", SymbolKind::Struct, (10, 25), None),
+        // No recorded parent: nested by line range
+        outline_symbol(4, "close", SymbolKind::Method, (21, 24), None),
+        outline_symbol(1, "VERSION", SymbolKind::Constant, (1, 1), None),
+    ];
+
+    let parents = parents(&symbols);
+    assert_eq!(parents.get(&SymbolId(3)), Some(&SymbolId(2)));
+    assert_eq!(parents.get(&SymbolId(4)), Some(&SymbolId(2)));
+    assert_eq!(parents.get(&SymbolId(5)), None);
+
+    let info = adi_core::FileInfo {
+        file: adi_core::File {
+            id: adi_core::FileId(1),
+            path: PathBuf::from("src/conn.rs"),
+            language: adi_core::Language::Rust,
+            hash: String::new(),
+            size: 0,
+            description: None,
+        },
+        symbols,
+    };
+    let outline = outline(&info);
+    let roots: Vec<&str> = outline.outline.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(roots, vec!["VERSION", "Conn", "helper"]);
+    let methods: Vec<&str> = outline.outline[1].children.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(methods, vec!["open", "close"]);
+
+    let open = &outline.outline[1].children[0];
+    assert_eq!(open.signature.as_deref(), Some("pub fn open(addr: &str) -> Self"));
+    assert_eq!(open.summary.as_deref(), Some("Opens a connection."));
+
+    let compact = outline.compact(&mut Keys::default());
+    assert!(compact.contains("\n    #3 12-20 "));
+    assert!(compact.contains("pub fn open(addr: &str) -> Self — Opens a connection.\n"));
+}
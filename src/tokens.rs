@@ -0,0 +1,29 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Offline token estimates for fitting output into a context budget.
+
+/// Approximate token count of `text` for typical BPE tokenizers.
+///
+/// Counts roughly four characters of prose per token, but at least one token
+/// per word or symbol run, since code is denser than prose.
+pub fn estimate_tokens(text: &str) -> usize {
+    let chars = text.chars().count();
+    let mut pieces = 0;
+    let mut in_word = false;
+    for c in text.chars() {
+        if c.is_alphanumeric() || c == '_' {
+            if !in_word {
+                pieces += 1;
+            }
+            in_word = true;
+        } else {
+            in_word = false;
+            if !c.is_whitespace() {
+                pieces += 1;
+            }
+        }
+    }
+    chars.div_ceil(4).max(pieces * 3 / 4)
+}
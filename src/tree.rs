@@ -0,0 +1,257 @@
+// Copyright (c) 2024-2025 Ihor
+// SPDX-License-Identifier: BSL-1.1
+// See LICENSE file for details
+
+//! Filtered project trees and directory listings for top-down exploration.
+
+use adi_core::{FileNode, SymbolKind, SymbolNode, Tree};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::filter::string_list;
+use crate::JsonRpcError;
+
+/// Every symbol kind, for validating `kind` filters.
+const SYMBOL_KINDS: &[SymbolKind] = &[
+    SymbolKind::Function,
+    SymbolKind::Method,
+    SymbolKind::Class,
+    SymbolKind::Struct,
+    SymbolKind::Enum,
+    SymbolKind::Interface,
+    SymbolKind::Trait,
+    SymbolKind::Module,
+    SymbolKind::Constant,
+    SymbolKind::Variable,
+    SymbolKind::Type,
+    SymbolKind::Property,
+    SymbolKind::Field,
+    SymbolKind::Constructor,
+    SymbolKind::EnumVariant,
+    SymbolKind::Unknown,
+];
+
+#[derive(Debug, Default)]
+pub struct TreeOptions {
+    /// Only files under this directory
+    pub path: Option<PathBuf>,
+    /// Directory levels below `path` to expand; deeper directories are collapsed
+    pub max_depth: Option<usize>,
+    /// Symbol kinds to keep; empty for all
+    pub kinds: Vec<String>,
+    pub symbols: bool,
+    pub languages: Vec<String>,
+    /// Directories with more files than this are collapsed into counts
+    pub collapse_above: Option<usize>,
+}
+
+impl TreeOptions {
+    /// Reads the options from tool arguments or resource query parameters.
+    pub fn from_arguments(arguments: &Value) -> Result<Self, JsonRpcError> {
+        let list = |key: &str| -> Vec<String> {
+            string_list(arguments.get(key))
+                .iter()
+                .flat_map(|s| s.split(','))
+                .map(|s| s.trim().to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect()
+        };
+        let symbols = match arguments.get("symbols") {
+            None | Some(Value::Null) => true,
+            Some(value) => value
+                .as_bool()
+                .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+                .ok_or_else(|| JsonRpcError {
+                    code: -32602,
+                    message: format!("Invalid symbols: {} (expected true or false)", value),
+                    data: None,
+                })?,
+        };
+
+        let kinds = list("kind");
+        if let Some(unknown) = kinds.iter().find(|k| !SYMBOL_KINDS.iter().any(|kind| kind.as_str() == k.as_str())) {
+            return Err(JsonRpcError {
+                code: -32602,
+                message: format!(
+                    "Unknown kind: {} (expected one of: {})",
+                    unknown,
+                    SYMBOL_KINDS.iter().map(|k| k.as_str()).collect::<Vec<_>>().join(", ")
+                ),
+                data: None,
+            });
+        }
+
+        Ok(Self {
+            path: arguments.get("path").and_then(|v| v.as_str()).and_then(normalize_prefix),
+            max_depth: arguments.get("depth").and_then(|v| v.as_u64()).map(|d| d as usize),
+            kinds,
+            symbols,
+            languages: list("language"),
+            collapse_above: arguments.get("collapse_above").and_then(|v| v.as_u64()).map(|n| n.max(1) as usize),
+        })
+    }
+
+    fn includes(&self, file: &FileNode) -> bool {
+        let root = self.path.as_deref().unwrap_or(Path::new(""));
+        file.path.starts_with(root)
+            && (self.languages.is_empty() || self.languages.iter().any(|l| l.eq_ignore_ascii_case(file.language.as_str())))
+    }
+}
+
+/// Directory prefix from a user-supplied path; `None` for the project root.
+pub fn normalize_prefix(path: &str) -> Option<PathBuf> {
+    let path = path.trim_start_matches("./").trim_matches('/');
+    (!path.is_empty() && path != ".").then(|| PathBuf::from(path))
+}
+
+/// File, symbol and language counts of a directory, in place of its contents.
+#[derive(Debug, Clone, Serialize)]
+pub struct DirectorySummary {
+    pub path: String,
+    pub files: usize,
+    pub symbols: usize,
+    pub languages: BTreeMap<String, usize>,
+}
+
+impl DirectorySummary {
+    fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_string_lossy().to_string(),
+            files: 0,
+            symbols: 0,
+            languages: BTreeMap::new(),
+        }
+    }
+
+    fn add(&mut self, file: &FileNode) {
+        self.files += 1;
+        self.symbols += count_symbols(&file.symbols);
+        *self.languages.entry(file.language.as_str().to_string()).or_default() += 1;
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FilteredTree {
+    pub files: Vec<FileNode>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub collapsed: Vec<DirectorySummary>,
+}
+
+/// Symbols in a subtree, nested ones included.
+pub fn count_symbols(nodes: &[SymbolNode]) -> usize {
+    nodes.iter().map(|n| 1 + count_symbols(&n.children)).sum()
+}
+
+/// Keeps nodes of the wanted kinds; children of dropped nodes move up a level.
+fn filter_symbols(nodes: Vec<SymbolNode>, kinds: &[String]) -> Vec<SymbolNode> {
+    let mut kept = Vec::new();
+    for mut node in nodes {
+        let children = filter_symbols(std::mem::take(&mut node.children), kinds);
+        if kinds.iter().any(|k| k.eq_ignore_ascii_case(node.kind.as_str())) {
+            node.children = children;
+            kept.push(node);
+        } else {
+            kept.extend(children);
+        }
+    }
+    kept
+}
+
+/// Directories containing `path` below `root`, shallowest first.
+fn ancestors(root: &Path, path: &Path) -> Vec<PathBuf> {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let mut ancestors: Vec<PathBuf> = relative
+        .ancestors()
+        .skip(1)
+        .filter(|a| !a.as_os_str().is_empty())
+        .map(|a| root.join(a))
+        .collect();
+    ancestors.reverse();
+    ancestors
+}
+
+/// Applies the options to the project tree.
+///
+/// A file under a directory deeper than `max_depth`, or in one with more than
+/// `collapse_above` files, is counted in the shallowest such directory
+/// instead of being listed.
+pub fn filter_tree(tree: Tree, options: &TreeOptions) -> FilteredTree {
+    let root = options.path.clone().unwrap_or_default();
+    let files: Vec<FileNode> = tree.files.into_iter().filter(|f| options.includes(f)).collect();
+
+    let mut directory_sizes: HashMap<PathBuf, usize> = HashMap::new();
+    for file in &files {
+        for directory in ancestors(&root, &file.path) {
+            *directory_sizes.entry(directory).or_default() += 1;
+        }
+    }
+
+    let mut collapsed: BTreeMap<PathBuf, DirectorySummary> = BTreeMap::new();
+    let mut listed = Vec::new();
+    for mut file in files {
+        let collapse_at = ancestors(&root, &file.path).into_iter().enumerate().find(|(level, directory)| {
+            options.max_depth.is_some_and(|depth| *level >= depth)
+                || options.collapse_above.is_some_and(|limit| directory_sizes[directory] > limit)
+        });
+        if let Some((_, directory)) = collapse_at {
+            collapsed.entry(directory.clone()).or_insert_with(|| DirectorySummary::new(&directory)).add(&file);
+            continue;
+        }
+
+        if !options.symbols {
+            file.symbols.clear();
+        } else if !options.kinds.is_empty() {
+            file.symbols = filter_symbols(std::mem::take(&mut file.symbols), &options.kinds);
+        }
+        listed.push(file);
+    }
+
+    FilteredTree {
+        files: listed,
+        collapsed: collapsed.into_values().collect(),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileEntry {
+    pub path: String,
+    pub language: String,
+    pub symbols: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DirectoryListing {
+    pub path: String,
+    pub directories: Vec<DirectorySummary>,
+    pub files: Vec<FileEntry>,
+}
+
+/// Immediate children of the options' `path`: subdirectories with counts, and files.
+pub fn list_directory(tree: &Tree, options: &TreeOptions) -> DirectoryListing {
+    let root = options.path.clone().unwrap_or_default();
+    let mut directories: BTreeMap<PathBuf, DirectorySummary> = BTreeMap::new();
+    let mut files = Vec::new();
+
+    for file in &tree.files {
+        if !options.includes(file) {
+            continue;
+        }
+        match ancestors(&root, &file.path).into_iter().next() {
+            Some(child) => directories.entry(child.clone()).or_insert_with(|| DirectorySummary::new(&child)).add(file),
+            None => files.push(FileEntry {
+                path: file.path.to_string_lossy().to_string(),
+                language: file.language.as_str().to_string(),
+                symbols: count_symbols(&file.symbols),
+            }),
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    DirectoryListing {
+        path: root.to_string_lossy().to_string(),
+        directories: directories.into_values().collect(),
+        files,
+    }
+}